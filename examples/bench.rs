@@ -0,0 +1,35 @@
+//Compare the AST-building `evaluate` with the single-pass `evaluate_fast`.
+//
+//        cargo run --release --example bench
+use std::time::Instant;
+
+use rust_calculator::{evaluate, evaluate_fast, CalcError, Value};
+
+const EXPRESSIONS: [&str; 5] = [
+    "1 + 2 * 3",
+    "7 + 3 * (10 / (12 / (3 + 1) - 1))",
+    "2 ^ 10 - 3 ^ 4 / 9",
+    "sin(pi / 4) ^ 2 + cos(pi / 4) ^ 2",
+    "-(1.5 + 2.25) * 4 - 8 / 2",
+];
+
+const ROUNDS: usize = 200_000;
+
+fn time(name: &str, evaluator: fn(&str) -> Result<Value, CalcError>)
+{
+    let start = Instant::now();
+    for _ in 0..ROUNDS{
+        for expression in EXPRESSIONS.iter(){
+            evaluator(expression).expect("benchmark expressions are valid");
+        }
+    }
+    let elapsed = start.elapsed();
+    let per_expression = elapsed.as_nanos() / (ROUNDS * EXPRESSIONS.len()) as u128;
+    println!("{:<14} {:>8.2?} total, {:>5} ns per expression", name, elapsed, per_expression);
+}
+
+fn main()
+{
+    time("evaluate", |input| evaluate(input).map(|outcome| outcome.value));
+    time("evaluate_fast", evaluate_fast);
+}
@@ -0,0 +1,449 @@
+use std::collections::BTreeSet;
+
+use crate::functions::{constant, find_builtin, Arity};
+use crate::token::Token;
+
+//Abstract syntax tree produced by the parser.
+//
+//        Operators keep the token they were parsed from, so
+//        `2 + 3 * 4` becomes
+//        BinOp(Integer(2), Plus, BinOp(Integer(3), Mul, Integer(4))).
+//
+//        New syntax adds nodes, so a `match` outside this crate needs a `_`
+//        arm.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub enum Ast
+{
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Variable(String),
+    UnaryOp(Token, Box<Ast>),
+    BinOp(Box<Ast>, Token, Box<Ast>),
+    Call(String, Vec<Ast>),
+    Assign(String, Box<Ast>),
+    //`if condition then a else b`.
+    If(Box<Ast>, Box<Ast>, Box<Ast>),
+    //A user function definition: name, parameter names and body.
+    FunctionDef(String, Vec<String>, Box<Ast>)
+}
+
+//Number of levels in the tree; a lone number has height 1. Walks with an
+//explicit stack so it is safe to call on trees of any depth.
+pub fn height(ast: &Ast) -> usize
+{
+    let mut deepest = 0;
+    let mut pending = vec![(ast, 1)];
+
+    while let Some((node, depth)) = pending.pop(){
+        deepest = deepest.max(depth);
+        match node
+        {
+            Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Variable(_) => {}
+            Ast::UnaryOp(_, operand) => pending.push((operand, depth + 1)),
+            Ast::BinOp(left, _, right) => {
+                pending.push((left, depth + 1));
+                pending.push((right, depth + 1));
+            }
+            Ast::Call(_, args) => pending.extend(args.iter().map(|arg| (arg, depth + 1))),
+            Ast::If(condition, then, otherwise) => pending.extend([condition, then, otherwise].map(|branch| (&**branch, depth + 1))),
+            Ast::Assign(_, value) => pending.push((value, depth + 1)),
+            Ast::FunctionDef(_, _, body) => pending.push((body, depth + 1)),
+        }
+    }
+    deepest
+}
+
+//Whether `name` could have been lexed as an identifier.
+fn is_name(name: &str) -> bool
+{
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_alphabetic() || first == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+//Check that `ast` is a tree the parser could have built, for catching
+//parser bugs and malformed hand-built trees: operators are ones the
+//grammar has (so no `Eof` or `(` inside), names are identifiers (or `@`
+//and placeholders like `$1` for variables), and definitions and
+//assignments only appear where a statement can have them.
+pub fn validate(ast: &Ast) -> Result<(), String>
+{
+    //Each node with whether it is where a statement is, which is the only
+    //place a definition or (chained) assignment may be.
+    let mut pending = vec![(ast, true)];
+
+    while let Some((node, statement)) = pending.pop(){
+        match node
+        {
+            Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) => {}
+            Ast::Variable(name) => {
+                let placeholder = name.strip_prefix('$').is_some_and(|index| index.parse::<usize>().is_ok_and(|index| index > 0));
+                if !is_name(name) && name != "@" && !placeholder{
+                    return Err(format!("'{}' is not a variable name", name));
+                }
+            }
+            Ast::UnaryOp(operator, operand) => {
+                if !matches!(operator, Token::Plus | Token::Minus | Token::Not | Token::Percent){
+                    return Err(format!("'{}' is not a unary operator", operator));
+                }
+                pending.push((operand, false));
+            }
+            Ast::BinOp(left, operator, right) => {
+                let arithmetic = matches!(operator, Token::Plus | Token::Minus | Token::Mul | Token::Div | Token::Power | Token::Of);
+                if !arithmetic && !operator.is_comparison() && !operator.is_logical(){
+                    return Err(format!("'{}' is not a binary operator", operator));
+                }
+                pending.push((left, false));
+                pending.push((right, false));
+            }
+            Ast::Call(name, args) => {
+                if !is_name(name){
+                    return Err(format!("'{}' is not a function name", name));
+                }
+                pending.extend(args.iter().map(|arg| (arg, false)));
+            }
+            Ast::If(condition, then, otherwise) => pending.extend([condition, then, otherwise].map(|branch| (&**branch, false))),
+            Ast::Assign(name, value) => {
+                if !statement{
+                    return Err(format!("assignment to {} inside an expression", name));
+                }
+                if !is_name(name){
+                    return Err(format!("'{}' can't be assigned to", name));
+                }
+                pending.push((value, true));
+            }
+            Ast::FunctionDef(name, params, body) => {
+                if !statement{
+                    return Err(format!("definition of {} inside another statement", name));
+                }
+                if let Some(param) = std::iter::once(name).chain(params).find(|param| !is_name(param)){
+                    return Err(format!("'{}' is not a valid name in a definition", param));
+                }
+                pending.push((body, false));
+            }
+        }
+    }
+    Ok(())
+}
+
+//Which arguments of the special form `name` are evaluated with its
+//variable bound, as (variable, bound argument): sum(i, 1, 10, i ^ 2)
+//binds `i` in `i ^ 2` only.
+fn special_form_binding(name: &str, arg_count: usize) -> Option<(usize, usize)>
+{
+    match name
+    {
+        "sum" | "product" if arg_count == 4 => Some((0, 3)),
+        "deriv" if arg_count == 3 => Some((1, 0)),
+        _ => None,
+    }
+}
+
+fn collect_free<'a>(ast: &'a Ast, bound: &mut Vec<&'a str>, names: &mut BTreeSet<String>)
+{
+    match ast
+    {
+        Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) => {}
+        Ast::Variable(name) => {
+            if !bound.contains(&name.as_str()) && constant(name).is_none() && name != "mem" && name != "@"{
+                names.insert(name.clone());
+            }
+        }
+        Ast::UnaryOp(_, operand) => collect_free(operand, bound, names),
+        Ast::BinOp(left, _, right) => {
+            collect_free(left, bound, names);
+            collect_free(right, bound, names);
+        }
+        Ast::Call(name, args) => {
+            //The position of a special form's variable, its name and the
+            //position of the argument it is bound in.
+            let binding = find_builtin(name)
+                .filter(|builtin| builtin.lazy)
+                .and_then(|_| special_form_binding(name, args.len()))
+                .and_then(|(variable, body)| match &args[variable]
+                {
+                    Ast::Variable(variable_name) => Some((variable, variable_name.as_str(), body)),
+                    _ => None,
+                });
+            for (i, arg) in args.iter().enumerate(){
+                match binding
+                {
+                    Some((variable, _, _)) if i == variable => {}
+                    Some((_, variable_name, body)) if i == body => {
+                        bound.push(variable_name);
+                        collect_free(arg, bound, names);
+                        bound.pop();
+                    }
+                    _ => collect_free(arg, bound, names),
+                }
+            }
+        }
+        Ast::If(condition, then, otherwise) => {
+            collect_free(condition, bound, names);
+            collect_free(then, bound, names);
+            collect_free(otherwise, bound, names);
+        }
+        Ast::Assign(_, value) => collect_free(value, bound, names),
+        Ast::FunctionDef(_, params, body) => {
+            let depth = bound.len();
+            bound.extend(params.iter().map(String::as_str));
+            collect_free(body, bound, names);
+            bound.truncate(depth);
+        }
+    }
+}
+
+//The names `ast` reads as variables without defining them, sorted and
+//without repeats. Constants, `mem`, function parameters and the
+//variables bound by `sum`, `product` and `deriv` are left out, as are
+//the names being assigned to.
+pub fn free_variables(ast: &Ast) -> Vec<String>
+{
+    let mut names = BTreeSet::new();
+    collect_free(ast, &mut Vec::new(), &mut names);
+    names.into_iter().collect()
+}
+
+fn render_node(ast: &Ast, depth: usize, out: &mut String)
+{
+    out.push_str(&"  ".repeat(depth));
+
+    match ast
+    {
+        Ast::Integer(integer) => out.push_str(&format!("Integer({})\n", integer)),
+        Ast::Float(float) => out.push_str(&format!("Float({})\n", float)),
+        Ast::Bool(boolean) => out.push_str(&format!("Bool({})\n", boolean)),
+        Ast::Variable(name) => out.push_str(&format!("Variable({})\n", name)),
+        Ast::UnaryOp(operator, operand) => {
+            out.push_str(&format!("UnaryOp({})\n", operator.to_str()));
+            render_node(operand, depth + 1, out);
+        }
+        Ast::BinOp(left, operator, right) => {
+            out.push_str(&format!("BinOp({})\n", operator.to_str()));
+            render_node(left, depth + 1, out);
+            render_node(right, depth + 1, out);
+        }
+        Ast::Call(name, args) => {
+            out.push_str(&format!("Call({})\n", name));
+            for arg in args{
+                render_node(arg, depth + 1, out);
+            }
+        }
+        Ast::If(condition, then, otherwise) => {
+            out.push_str("If\n");
+            render_node(condition, depth + 1, out);
+            render_node(then, depth + 1, out);
+            render_node(otherwise, depth + 1, out);
+        }
+        Ast::Assign(name, value) => {
+            out.push_str(&format!("Assign({})\n", name));
+            render_node(value, depth + 1, out);
+        }
+        Ast::FunctionDef(name, params, body) => {
+            out.push_str(&format!("FunctionDef({}({}))\n", name, params.join(", ")));
+            render_node(body, depth + 1, out);
+        }
+    }
+}
+
+//Render `ast` as an indented outline, one node per line, children
+//indented two spaces below their parent:
+//
+//        BinOp(+)
+//          Integer(2)
+//          BinOp(*)
+//            Integer(3)
+//            Integer(4)
+pub fn render_tree(ast: &Ast) -> String
+{
+    let mut out = String::new();
+    render_node(ast, 0, &mut out);
+    out
+}
+
+//One node's symbol in Polish notation: operators as written, except
+//that unary `-` and `+` become `neg` and `pos` to tell them from the
+//binary ones, and calls to functions taking a variable number of
+//arguments (or your own) carry the count, as in `max/3`.
+fn symbol(ast: &Ast) -> String
+{
+    match ast
+    {
+        Ast::Integer(integer) => Token::Integer(*integer).to_str(),
+        Ast::Float(float) => Token::Float(*float).to_str(),
+        Ast::Bool(boolean) => boolean.to_string(),
+        Ast::Variable(name) => name.clone(),
+        Ast::UnaryOp(Token::Minus, _) => String::from("neg"),
+        Ast::UnaryOp(Token::Plus, _) => String::from("pos"),
+        Ast::UnaryOp(operator, _) | Ast::BinOp(_, operator, _) => operator.to_str(),
+        Ast::Call(name, args) => match find_builtin(name)
+        {
+            Some(builtin) if matches!(builtin.arity, Arity::Exactly(_)) => name.clone(),
+            _ => format!("{}/{}", name, args.len()),
+        },
+        Ast::If(_, _, _) => String::from("if"),
+        Ast::Assign(_, _) | Ast::FunctionDef(_, _, _) => String::from("="),
+    }
+}
+
+fn polish_node(ast: &Ast, postfix: bool, out: &mut Vec<String>)
+{
+    let operands: Vec<&Ast> = match ast
+    {
+        Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Variable(_) => Vec::new(),
+        Ast::UnaryOp(_, operand) => vec![operand],
+        Ast::BinOp(left, _, right) => vec![left, right],
+        Ast::Call(_, args) => args.iter().collect(),
+        Ast::If(condition, then, otherwise) => vec![condition, then, otherwise],
+        Ast::Assign(_, value) | Ast::FunctionDef(_, _, value) => vec![value],
+    };
+
+    if !postfix{
+        out.push(symbol(ast));
+    }
+    //The name being defined is the first operand of `=`.
+    match ast
+    {
+        Ast::Assign(name, _) => out.push(name.clone()),
+        Ast::FunctionDef(name, params, _) => out.push(format!("{}({})", name, params.join(", "))),
+        _ => {}
+    }
+    for operand in operands{
+        polish_node(operand, postfix, out);
+    }
+    if postfix{
+        out.push(symbol(ast));
+    }
+}
+
+//Render `ast` in prefix (Polish) notation, each operator before its
+//operands: `2 + 3 * 4` is `+ 2 * 3 4`.
+pub fn to_prefix(ast: &Ast) -> String
+{
+    let mut out = Vec::new();
+    polish_node(ast, false, &mut out);
+    out.join(" ")
+}
+
+//Render `ast` in postfix (reverse Polish) notation, each operator after
+//its operands: `2 + 3 * 4` is `2 3 4 * +` and `-(1 + 2)` is `1 2 + neg`.
+pub fn to_rpn(ast: &Ast) -> String
+{
+    let mut out = Vec::new();
+    polish_node(ast, true, &mut out);
+    out.join(" ")
+}
+
+fn parenthesize_node(ast: &Ast, top: bool) -> String
+{
+    let grouped = match ast
+    {
+        Ast::Integer(integer) => return Token::Integer(*integer).to_str(),
+        Ast::Float(float) => return Token::Float(*float).to_str(),
+        Ast::Bool(boolean) => return boolean.to_string(),
+        Ast::Variable(name) => return name.clone(),
+        Ast::Call(name, args) => {
+            let args = args.iter().map(|arg| parenthesize_node(arg, true)).collect::<Vec<String>>();
+            return format!("{}({})", name, args.join(", "));
+        }
+        Ast::Assign(name, value) => return format!("{} = {}", name, parenthesize_node(value, true)),
+        Ast::FunctionDef(name, params, body) => return format!("{}({}) = {}", name, params.join(", "), parenthesize_node(body, true)),
+        Ast::If(condition, then, otherwise) => format!("if {} then {} else {}",
+            parenthesize_node(condition, true), parenthesize_node(then, true), parenthesize_node(otherwise, true)),
+        Ast::UnaryOp(Token::Percent, operand) => format!("{}%", parenthesize_node(operand, false)),
+        Ast::UnaryOp(operator, operand) => format!("{}{}", operator.to_str(), parenthesize_node(operand, false)),
+        Ast::BinOp(left, operator, right) => {
+            format!("{} {} {}", parenthesize_node(left, false), operator.to_str(), parenthesize_node(right, false))
+        }
+    };
+
+    if top { grouped } else { format!("({})", grouped) }
+}
+
+//Render `ast` as source text with every operation inside another one
+//wrapped in parentheses, showing how precedence grouped it. The text
+//parses back to the same tree:
+//
+//        2 + 3 * 4      ->  2 + (3 * 4)
+//        -2 ^ 2         ->  -(2 ^ 2)
+//        2 ^ 3 ^ 2      ->  2 ^ (3 ^ 2)
+//        10% of 200 + 5 ->  ((10%) of 200) + 5
+//        1 + if c then 2 else 3 + 4  ->  1 + (if c then 2 else 3 + 4)
+pub fn parenthesize(ast: &Ast) -> String
+{
+    parenthesize_node(ast, true)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::parse;
+
+    use super::{free_variables, parenthesize, render_tree, to_prefix, to_rpn};
+
+    #[test]
+    fn trees_are_rendered_one_node_per_line()
+    {
+        assert_eq!(render_tree(&parse("2 + 3 * 4").unwrap()), "\
+BinOp(+)
+  Integer(2)
+  BinOp(*)
+    Integer(3)
+    Integer(4)
+");
+        assert_eq!(render_tree(&parse("x = -max(1, y)").unwrap()), "\
+Assign(x)
+  UnaryOp(-)
+    Call(max)
+      Integer(1)
+      Variable(y)
+");
+    }
+
+    #[test]
+    fn parenthesize_shows_the_grouping()
+    {
+        let cases = [
+            ("2 + 3 * 4", "2 + (3 * 4)"),
+            ("-2 ^ 2", "-(2 ^ 2)"),
+            ("2 ^ 3 ^ 2", "2 ^ (3 ^ 2)"),
+            ("10% of 200 + 5", "((10%) of 200) + 5"),
+            ("1 + if c then 2 else 3 + 4", "1 + (if c then 2 else 3 + 4)"),
+            ("(1 + 2) * 3", "(1 + 2) * 3"),
+            ("x = max(1, 2 * y)", "x = max(1, 2 * y)"),
+            ("f(a) = a - 1 - 2.0", "f(a) = (a - 1) - 2.0"),
+        ];
+        for (input, expected) in cases{
+            assert_eq!(parenthesize(&parse(input).unwrap()), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn free_variables_are_sorted_and_unique()
+    {
+        let free = |input: &str| free_variables(&parse(input).unwrap());
+        assert_eq!(free("x + 2*y - x"), vec!["x", "y"]);
+        assert_eq!(free("pi * r ^ 2 + mem"), vec!["r"]);
+        assert_eq!(free("sum(i, 1, n, i * k)"), vec!["k", "n"]);
+        assert_eq!(free("z = f(a, 2)"), vec!["a"]);
+        assert!(free("g(t) = t * 2").is_empty());
+    }
+
+    #[test]
+    fn postfix_and_prefix_renderings()
+    {
+        let cases = [
+            ("2 + 3 * 4", "2 3 4 * +", "+ 2 * 3 4"),
+            ("-(1 + 2)", "1 2 + neg", "neg + 1 2"),
+            ("max(1, 2, 3) ^ 2", "1 2 3 max/3 2 ^", "^ max/3 1 2 3 2"),
+            ("if a < b then a else b", "a b < a b if", "if < a b a b"),
+            ("10% of 200", "10 % 200 of", "of % 10 200"),
+        ];
+        for (input, rpn, prefix) in cases{
+            let ast = parse(input).unwrap();
+            assert_eq!(to_rpn(&ast), rpn, "{}", input);
+            assert_eq!(to_prefix(&ast), prefix, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::error::CalcError;
+use crate::lexer::Lexer;
+use crate::token::Token;
+
+//Check that the parentheses in `input` are balanced and properly nested,
+//without parsing it. Meant for quick validation while the user is typing.
+//
+//        A stray `)` is reported at its own position; a missing `)` is
+//        reported at the position of the first `(` left unclosed.
+pub fn check_balanced(input: &str) -> Result<(), CalcError>
+{
+    let mut lexer = Lexer::create_lexer(String::from(input));
+    let mut open = Vec::new();
+
+    loop {
+        match lexer.get_next_token()?
+        {
+            Token::Lparen => open.push(lexer.token_start()),
+            Token::Rparen => {
+                open.pop().ok_or_else(|| CalcError::UnmatchedDelimiter(')', lexer.token_start()))?;
+            }
+            Token::Eof => break,
+            _ => {}
+        }
+    }
+
+    match open.first()
+    {
+        Some(pos) => Err(CalcError::UnmatchedDelimiter('(', *pos)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::CalcError;
+
+    use super::check_balanced;
+
+    #[test]
+    fn balanced_input_is_accepted()
+    {
+        for input in ["", "1 + 2", "(1 + 2) * 3", "max((1), (2 * (3)))"]{
+            assert_eq!(check_balanced(input), Ok(()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn a_missing_closer_is_reported_at_the_first_unclosed_opener()
+    {
+        assert_eq!(check_balanced("(1 + 2"), Err(CalcError::UnmatchedDelimiter('(', 0)));
+        assert_eq!(check_balanced("1 + ((2) * (3"), Err(CalcError::UnmatchedDelimiter('(', 4)));
+    }
+
+    #[test]
+    fn a_stray_closer_is_reported_at_its_own_position()
+    {
+        assert_eq!(check_balanced("1 + 2)"), Err(CalcError::UnmatchedDelimiter(')', 5)));
+        assert_eq!(check_balanced("(1)) + (2"), Err(CalcError::UnmatchedDelimiter(')', 3)));
+    }
+}
@@ -0,0 +1,292 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::error::CalcError;
+
+//Largest result, in bits, that `pow` is allowed to build. Keeps a typo
+//like `2 ^ 99999999999` from exhausting memory.
+const MAX_BITS: u64 = 1 << 20;
+
+//Arbitrary-precision integer used by the `bignum` feature when a result
+//doesn't fit in an i64.
+//
+//        The magnitude is stored as little-endian base 2^32 limbs with no
+//        trailing zero limbs; zero is an empty magnitude and never negative.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct BigInt
+{
+    negative: bool,
+    magnitude: Vec<u32>
+}
+
+fn trim(mut magnitude: Vec<u32>) -> Vec<u32>
+{
+    while magnitude.last() == Some(&0){
+        magnitude.pop();
+    }
+    magnitude
+}
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering
+{
+    if a.len() != b.len(){
+        return a.len().cmp(&b.len());
+    }
+    for (x, y) in a.iter().rev().zip(b.iter().rev()){
+        if x != y{
+            return x.cmp(y);
+        }
+    }
+    Ordering::Equal
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32>
+{
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()){
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    trim(result)
+}
+
+//`a - b`, where `a` must not be smaller than `b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32>
+{
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, limb) in a.iter().enumerate(){
+        let mut difference = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0{
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(result)
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32>
+{
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate(){
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate(){
+            let product = *x as u64 * *y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    trim(result)
+}
+
+fn bit_length(magnitude: &[u32]) -> u64
+{
+    match magnitude.last()
+    {
+        Some(top) => (magnitude.len() as u64 - 1) * 32 + (32 - top.leading_zeros() as u64),
+        None => 0,
+    }
+}
+
+//Schoolbook binary long division of two magnitudes.
+fn divrem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>)
+{
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+
+    for bit in (0..bit_length(a)).rev(){
+        remainder = add_magnitude(&remainder, &remainder);
+        if (a[(bit / 32) as usize] >> (bit % 32)) & 1 == 1{
+            remainder = add_magnitude(&remainder, &[1]);
+        }
+        if compare_magnitude(&remainder, b) != Ordering::Less{
+            remainder = sub_magnitude(&remainder, b);
+            quotient[(bit / 32) as usize] |= 1 << (bit % 32);
+        }
+    }
+
+    (trim(quotient), remainder)
+}
+
+//Divide a magnitude by a small divisor in place, returning the remainder.
+fn divrem_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32
+{
+    let mut remainder = 0u64;
+    for limb in magnitude.iter_mut().rev(){
+        let current = (remainder << 32) | *limb as u64;
+        *limb = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    let trimmed = trim(std::mem::take(magnitude));
+    *magnitude = trimmed;
+    remainder as u32
+}
+
+impl BigInt
+{
+    fn create_bigint(negative: bool, magnitude: Vec<u32>) -> BigInt
+    {
+        let magnitude = trim(magnitude);
+        BigInt{
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn from_i64(value: i64) -> BigInt
+    {
+        let absolute = value.unsigned_abs();
+        BigInt::create_bigint(value < 0, vec![absolute as u32, (absolute >> 32) as u32])
+    }
+
+    //The value as an i64, if it fits.
+    pub fn to_i64(&self) -> Option<i64>
+    {
+        if self.magnitude.len() > 2{
+            return None;
+        }
+        let absolute = *self.magnitude.first().unwrap_or(&0) as u64
+            | (*self.magnitude.get(1).unwrap_or(&0) as u64) << 32;
+        if self.negative{
+            0i64.checked_sub_unsigned(absolute)
+        } else {
+            i64::try_from(absolute).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64
+    {
+        let absolute = self.magnitude.iter().rev().fold(0.0, |acc, limb| acc * 4294967296.0 + *limb as f64);
+        if self.negative { -absolute } else { absolute }
+    }
+
+    pub fn compare(&self, other: &BigInt) -> Ordering
+    {
+        match (self.negative, other.negative)
+        {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+
+    pub fn neg(&self) -> BigInt
+    {
+        BigInt::create_bigint(!self.negative, self.magnitude.clone())
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt
+    {
+        if self.negative == other.negative{
+            return BigInt::create_bigint(self.negative, add_magnitude(&self.magnitude, &other.magnitude));
+        }
+        match compare_magnitude(&self.magnitude, &other.magnitude)
+        {
+            Ordering::Less => BigInt::create_bigint(other.negative, sub_magnitude(&other.magnitude, &self.magnitude)),
+            _ => BigInt::create_bigint(self.negative, sub_magnitude(&self.magnitude, &other.magnitude)),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> BigInt
+    {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &BigInt) -> BigInt
+    {
+        BigInt::create_bigint(self.negative != other.negative, mul_magnitude(&self.magnitude, &other.magnitude))
+    }
+
+    //Truncating division, matching the integer `/` operator.
+    pub fn div(&self, other: &BigInt) -> Result<BigInt, CalcError>
+    {
+        if other.magnitude.is_empty(){
+            return Err(CalcError::DivisionByZero);
+        }
+        let (quotient, _) = divrem_magnitude(&self.magnitude, &other.magnitude);
+        Ok(BigInt::create_bigint(self.negative != other.negative, quotient))
+    }
+
+    //Truncating division with the remainder, which takes the sign of the
+    //dividend, matching `divmod`.
+    pub fn divmod(&self, other: &BigInt) -> Result<(BigInt, BigInt), CalcError>
+    {
+        if other.magnitude.is_empty(){
+            return Err(CalcError::DivisionByZero);
+        }
+        let (quotient, remainder) = divrem_magnitude(&self.magnitude, &other.magnitude);
+        Ok((BigInt::create_bigint(self.negative != other.negative, quotient), BigInt::create_bigint(self.negative, remainder)))
+    }
+
+    pub fn pow(&self, exponent: u64) -> Result<BigInt, CalcError>
+    {
+        if bit_length(&self.magnitude).saturating_sub(1).saturating_mul(exponent) > MAX_BITS{
+            return Err(CalcError::Overflow);
+        }
+
+        let mut result = BigInt::from_i64(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0{
+            if exponent & 1 == 1{
+                result = result.mul(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0{
+                base = base.mul(&base);
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn parse(text: &str) -> Option<BigInt>
+    {
+        let (negative, digits) = match text.strip_prefix('-')
+        {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()){
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+        for digit in digits.chars(){
+            magnitude = add_magnitude(&mul_magnitude(&magnitude, &[10]), &[digit.to_digit(10).unwrap()]);
+        }
+        Some(BigInt::create_bigint(negative, magnitude))
+    }
+}
+
+impl fmt::Display for BigInt
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        if self.magnitude.is_empty(){
+            return write!(f, "0");
+        }
+
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = Vec::new();
+        while !magnitude.is_empty(){
+            chunks.push(divrem_small(&mut magnitude, 1_000_000_000));
+        }
+
+        if self.negative{
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev(){
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
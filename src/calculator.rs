@@ -0,0 +1,708 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::ast::{parenthesize, Ast};
+use crate::environment::{Environment, NativeFunction, UserFunction};
+use crate::error::CalcError;
+use crate::functions::Arity;
+use crate::interpreter::{binary_op, Interpreter};
+use crate::options::{Angle, Base, GroupSeparator, Options, Rounding};
+use crate::lexer::{reassemble, Lexer};
+use crate::parser::Parser;
+use crate::random::Rng;
+use crate::snapshot::{parse_definition, HistoryEntry, Snapshot};
+use crate::token::Token;
+use crate::value::{StatementResult, Value};
+
+//A calculator session: the variables and functions defined so far,
+//the history of evaluated inputs and the memory register, kept alive
+//across calls to `eval`.
+pub struct Calculator
+{
+    env: Environment,
+    history: Vec<HistoryEntry>,
+    options: Options,
+    //The options the calculator was created with, restored by `reset`.
+    defaults: Options,
+    //Notes raised by the most recent evaluation.
+    notes: Vec<String>,
+    //The input of the most recent `eval`, reassembled from its tokens.
+    expression: String,
+    //Whether the most recent evaluation lost information.
+    lossy: bool,
+    //Where the token trace goes instead of stderr.
+    trace: Option<Rc<RefCell<dyn Write>>>
+}
+
+//Passes a calculator's trace writer to each lexer it creates.
+struct SharedTrace(Rc<RefCell<dyn Write>>);
+
+impl Write for SharedTrace
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.0.borrow_mut().flush()
+    }
+}
+
+impl Calculator
+{
+    pub fn new() -> Calculator
+    {
+        Calculator::with_options(Options::default())
+    }
+
+    //Start configuring a calculator with non-default settings:
+    //
+    //        Calculator::builder().base(Base::Hex).angle(Angle::Degrees).build()
+    pub fn builder() -> CalculatorBuilder
+    {
+        CalculatorBuilder{
+            options: Options::default(),
+            trace: None,
+        }
+    }
+
+    pub fn with_options(options: Options) -> Calculator
+    {
+        let mut env = Environment::create_environment();
+        if let Some(seed) = options.seed{
+            env.rng = Rng::create_rng(seed);
+        }
+
+        Calculator{
+            env,
+            history: Vec::new(),
+            defaults: options.clone(),
+            options,
+            notes: Vec::new(),
+            expression: String::new(),
+            lossy: false,
+            trace: None,
+        }
+    }
+
+    //A lexer for `input` with this calculator's options and trace writer.
+    fn lexer(&self, input: &str) -> Lexer
+    {
+        let mut lexer = Lexer::create_lexer_with_options(String::from(input), &self.options);
+        if let (true, Some(trace)) = (self.options.trace_tokens, &self.trace){
+            lexer.trace_to(Box::new(SharedTrace(Rc::clone(trace))));
+        }
+        lexer
+    }
+
+    //The functions of this session, which replace any built-ins by the
+    //same names.
+    fn function_names(&self) -> Vec<String>
+    {
+        self.env.functions.keys().chain(self.env.natives.keys()).cloned().collect()
+    }
+
+    fn execute(&mut self, input: &str) -> Result<StatementResult, CalcError>
+    {
+        self.notes.clear();
+        self.expression.clear();
+        self.lossy = false;
+        self.options.check_input(input)?;
+        let mut lexer = self.lexer(input);
+        lexer.record_tokens();
+        let node = Parser::create_parser(&mut lexer)?.shadowing(self.function_names()).parse()?;
+        self.expression = reassemble(&lexer.take_recorded());
+        self.run(&node)
+    }
+
+    //Evaluate a parsed statement, adding to the notes and lossy flag of
+    //the current evaluation.
+    fn run(&mut self, node: &Ast) -> Result<StatementResult, CalcError>
+    {
+        let mut interpreter = Interpreter::create_interpreter(&mut self.env, &self.options);
+        let result = interpreter.run(node);
+        self.notes.extend(interpreter.take_notes());
+        self.lossy |= interpreter.lossy();
+        result
+    }
+
+    fn record(&mut self, input: &str, result: &StatementResult)
+    {
+        if let Some(value) = result.value(){
+            self.history.push(HistoryEntry{
+                input: String::from(input),
+                result: value.clone(),
+            });
+        }
+    }
+
+    //Evaluate one statement (an expression, an assignment or a function
+    //definition) and record its value in the history if it succeeds.
+    pub fn eval(&mut self, input: &str) -> Result<StatementResult, CalcError>
+    {
+        let input = input.trim();
+        let result = self.execute(input)?;
+        self.record(input, &result);
+        Ok(result)
+    }
+
+    //Evaluate `;`-separated statements one at a time, handing each one's
+    //source text and result to `on_result` as soon as it is known, so a
+    //consumer can show results while a long input is still being read:
+    //
+    //        calculator.eval_statements("x = 2; x * 3", |statement, result| ...)
+    //
+    //        reports `x = 2` and then 6. A statement that fails, to parse or
+    //        to evaluate, doesn't stop the ones after it: `1 + 1; 2 +; 2 + 2`
+    //        reports 2, an error for `2 +`, and 4. Values are recorded in
+    //        the history as by `eval`, and `notes` collects the notes of all
+    //        the statements.
+    //
+    //        `@` is the value of the last statement before it in the same
+    //        input that produced one, so `5; @ + 3; @ * 2` gives 5, 8 and 16.
+    //        Failed statements and definitions leave it unchanged, and it
+    //        starts out undefined in every input.
+    pub fn eval_statements<F>(&mut self, input: &str, mut on_result: F)
+        where F: FnMut(&str, Result<StatementResult, CalcError>)
+    {
+        self.notes.clear();
+        self.lossy = false;
+        if let Err(e) = self.options.check_input(input){
+            return on_result(input.trim(), Err(e));
+        }
+
+        let text: Vec<char> = input.chars().collect();
+        let mut lexer = self.lexer(input);
+        let mut parser = Parser::create_statement_parser(&mut lexer).shadowing(self.function_names());
+        self.env.previous = None;
+        loop {
+            let start = parser.position();
+            let (node, span) = match parser.next_statement()
+            {
+                Ok(Some(statement)) => statement,
+                Ok(None) => break,
+                Err(e) => {
+                    parser.recover();
+                    let statement: String = text[start..parser.position()].iter().collect();
+                    on_result(statement.trim_start_matches(|c: char| c == ';' || c.is_whitespace()).trim_end(), Err(e));
+                    continue;
+                }
+            };
+
+            let statement: String = text[span].iter().collect();
+            let statement = statement.trim();
+            let result = self.run(&node);
+            if let Ok(result) = &result{
+                self.record(statement, result);
+                if let Some(value) = result.value(){
+                    self.env.previous = Some(value.clone());
+                }
+            }
+            on_result(statement, result);
+        }
+        self.env.previous = None;
+    }
+
+    //Evaluate a comma-separated list of expressions, such as
+    //`1 + 1, 2 * 2, 3 ^ 2`, returning their values in order. Nothing is
+    //recorded in the history.
+    pub fn eval_list(&mut self, input: &str) -> Result<Vec<Value>, CalcError>
+    {
+        self.notes.clear();
+        self.lossy = false;
+        self.options.check_input(input)?;
+        let mut lexer = self.lexer(input);
+        let nodes = Parser::create_parser(&mut lexer)?.shadowing(self.function_names()).parse_list()?;
+
+        let mut interpreter = Interpreter::create_interpreter(&mut self.env, &self.options);
+        let values = nodes.iter().map(|node| interpreter.visit(node)).collect();
+        self.notes = interpreter.take_notes();
+        self.lossy = interpreter.lossy();
+        values
+    }
+
+    //Evaluate every line of `text` in order, as if typed one after the
+    //other, skipping blank lines and `#` comments. Variables defined by
+    //earlier lines stay available to later ones and to any subsequent
+    //call to `eval`.
+    pub fn eval_lines(&mut self, text: &str) -> Vec<Result<StatementResult, CalcError>>
+    {
+        text.lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|line| self.eval(line))
+            .collect()
+    }
+
+    //Seed the session from a block of definitions, such as the contents
+    //of a startup file. Lines are evaluated like `eval_lines` but are not
+    //recorded in the history; a failing line doesn't stop the rest.
+    //Returns the failures together with their (1-based) line numbers.
+    pub fn load_definitions(&mut self, text: &str) -> Vec<(usize, CalcError)>
+    {
+        let mut errors = Vec::new();
+        for (number, line) in text.lines().enumerate(){
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#'){
+                continue;
+            }
+            if let Err(e) = self.execute(line){
+                errors.push((number + 1, e));
+            }
+        }
+        errors
+    }
+
+    pub fn options(&self) -> &Options
+    {
+        &self.options
+    }
+
+    //Display a result using this calculator's base and precision.
+    pub fn format(&self, result: &StatementResult) -> String
+    {
+        result.format(&self.options)
+    }
+
+    //Notes about the most recent evaluation that aren't errors, such as
+    //an integer division that was truncated.
+    pub fn notes(&self) -> &[String]
+    {
+        &self.notes
+    }
+
+    //Make `function` callable from expressions as `name(...)`, replacing
+    //any native function of that name. It takes precedence over a
+    //built-in of the same name, but not over a user-defined function.
+    //
+    //        calculator.register_function("double", Arity::Exactly(1), Box::new(|args| Ok(args[0] * 2.0)));
+    //
+    //        A call with a number of arguments `arity` doesn't accept is a
+    //        `WrongArgumentCount` error, so the function only ever receives
+    //        as many as it declared, converted to floats. Its result is a
+    //        float.
+    pub fn register_function(&mut self, name: &str, arity: Arity, function: NativeFunction)
+    {
+        self.env.natives.insert(String::from(name), (arity, function));
+    }
+
+    //The input of the most recent `eval` rewritten from its tokens, with
+    //canonical spacing, as reported by `evaluate`.
+    pub fn expression(&self) -> &str
+    {
+        &self.expression
+    }
+
+    //Whether the most recent evaluation lost information along the way,
+    //as reported by `evaluate_checked`.
+    pub fn lossy(&self) -> bool
+    {
+        self.lossy
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Value>
+    {
+        self.env.variables.get(name)
+    }
+
+    pub fn has_function(&self, name: &str) -> bool
+    {
+        self.env.functions.contains_key(name)
+    }
+
+    pub fn history(&self) -> &[HistoryEntry]
+    {
+        &self.history
+    }
+
+    //The result of the most recent successful evaluation.
+    pub fn last_result(&self) -> Option<&Value>
+    {
+        self.history.last().map(|entry| &entry.result)
+    }
+
+    fn update_memory(&mut self, operator: Token) -> Result<&Value, CalcError>
+    {
+        let last = self.last_result().ok_or(CalcError::NoPreviousResult)?;
+        self.env.memory = binary_op(&operator, &self.env.memory, last)?;
+        Ok(&self.env.memory)
+    }
+
+    //Add the last result to the memory register (M+).
+    pub fn memory_add(&mut self) -> Result<&Value, CalcError>
+    {
+        self.update_memory(Token::Plus)
+    }
+
+    //Subtract the last result from the memory register (M-).
+    pub fn memory_subtract(&mut self) -> Result<&Value, CalcError>
+    {
+        self.update_memory(Token::Minus)
+    }
+
+    //The memory register (MR). Expressions can also read it as `mem`.
+    pub fn memory(&self) -> &Value
+    {
+        &self.env.memory
+    }
+
+    //Reset the memory register to zero (MC).
+    pub fn memory_clear(&mut self)
+    {
+        self.env.memory = Value::Int(0);
+    }
+
+    //Restart the `random()` / `randint()` sequence from `seed`.
+    pub fn seed(&mut self, seed: u64)
+    {
+        self.options.seed = Some(seed);
+        self.env.rng = Rng::create_rng(seed);
+    }
+
+    //Show float results as fractions with denominators up to
+    //`max_denominator`, or as decimals again for `None`.
+    pub fn fractions(&mut self, max_denominator: Option<u64>)
+    {
+        self.options.max_denominator = max_denominator;
+    }
+
+    //Forget every variable, user-defined function, operator alias, history
+    //entry and the memory register, and go back to the other options the
+    //calculator was created with (which also restarts `random()`). Native
+    //functions registered by the host and the trace writer are kept.
+    pub fn reset(&mut self)
+    {
+        let natives = std::mem::take(&mut self.env.natives);
+        let trace = self.trace.take();
+        let mut defaults = self.defaults.clone();
+        defaults.operator_aliases.clear();
+        *self = Calculator::with_options(defaults);
+        self.env.natives = natives;
+        self.trace = trace;
+    }
+
+    //Capture the current variables and functions (each sorted by name),
+    //the memory register and the history.
+    pub fn snapshot(&self) -> Snapshot
+    {
+        let mut variables: Vec<(String, Value)> = self.env.variables.iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut functions: Vec<(&String, &UserFunction)> = self.env.functions.iter().collect();
+        functions.sort_by(|a, b| a.0.cmp(b.0));
+        let functions = functions.into_iter()
+            .map(|(name, function)| parenthesize(&Ast::FunctionDef(name.clone(), function.params.clone(), Box::new(function.body.clone()))))
+            .collect();
+
+        Snapshot{
+            variables,
+            functions,
+            memory: self.env.memory.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    //Replace the current variables, functions, memory register and history
+    //with those of `snapshot`. Fails, changing nothing, if one of its
+    //function definitions doesn't parse.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), CalcError>
+    {
+        let functions = snapshot.functions.iter()
+            .map(|definition| parse_definition(definition))
+            .collect::<Result<HashMap<String, UserFunction>, CalcError>>()?;
+
+        self.env.variables = snapshot.variables.iter().cloned().collect();
+        self.env.functions = functions;
+        self.env.memory = snapshot.memory.clone();
+        self.history = snapshot.history.clone();
+        Ok(())
+    }
+}
+
+impl Default for Calculator
+{
+    fn default() -> Calculator
+    {
+        Calculator::new()
+    }
+}
+
+//Settings for a new `Calculator`, created by `Calculator::builder`.
+pub struct CalculatorBuilder
+{
+    options: Options,
+    trace: Option<Rc<RefCell<dyn Write>>>
+}
+
+impl CalculatorBuilder
+{
+    pub fn base(mut self, base: Base) -> CalculatorBuilder
+    {
+        self.options.base = base;
+        self
+    }
+
+    pub fn angle(mut self, angle: Angle) -> CalculatorBuilder
+    {
+        self.options.angle = angle;
+        self
+    }
+
+    pub fn precision(mut self, digits: usize) -> CalculatorBuilder
+    {
+        self.options.precision = Some(digits);
+        self
+    }
+
+    pub fn rounding(mut self, rounding: Rounding) -> CalculatorBuilder
+    {
+        self.options.rounding = rounding;
+        self
+    }
+
+    pub fn fractions(mut self, max_denominator: u64) -> CalculatorBuilder
+    {
+        self.options.max_denominator = Some(max_denominator);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> CalculatorBuilder
+    {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> CalculatorBuilder
+    {
+        self.options.strict = strict;
+        self
+    }
+
+    pub fn undefined_value(mut self, value: Value) -> CalculatorBuilder
+    {
+        self.options.undefined_value = Some(value);
+        self
+    }
+
+    pub fn treat_undefined_as_zero(mut self, lenient: bool) -> CalculatorBuilder
+    {
+        self.options.undefined_value = if lenient { Some(Value::Int(0)) } else { None };
+        self
+    }
+
+    pub fn group_separator(mut self, separator: GroupSeparator) -> CalculatorBuilder
+    {
+        self.options.group_separator = Some(separator);
+        self
+    }
+
+    pub fn operator_alias(mut self, spelling: &str, operator: Token) -> CalculatorBuilder
+    {
+        self.options.operator_aliases.push((String::from(spelling), operator));
+        self
+    }
+
+    pub fn trace_tokens(mut self, trace: bool) -> CalculatorBuilder
+    {
+        self.options.trace_tokens = trace;
+        self
+    }
+
+    //Trace tokens to `writer` rather than stderr. The caller can keep a
+    //clone of it to read the trace back:
+    //
+    //        let trace = Rc::new(RefCell::new(Vec::new()));
+    //        let calculator = Calculator::builder().trace_to(trace.clone()).build();
+    pub fn trace_to(mut self, writer: Rc<RefCell<dyn Write>>) -> CalculatorBuilder
+    {
+        self.options.trace_tokens = true;
+        self.trace = Some(writer);
+        self
+    }
+
+    pub fn max_input_len(mut self, limit: usize) -> CalculatorBuilder
+    {
+        self.options.max_input_len = limit;
+        self
+    }
+
+    pub fn time_limit(mut self, limit: Duration) -> CalculatorBuilder
+    {
+        self.options.time_limit = Some(limit);
+        self
+    }
+
+    pub fn build(self) -> Calculator
+    {
+        let mut calculator = Calculator::with_options(self.options);
+        calculator.trace = self.trace;
+        calculator
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::{Angle, Arity, Base, CalcError, Calculator, StatementResult, Token, Value};
+
+    fn value(calculator: &mut Calculator, input: &str) -> Value
+    {
+        calculator.eval(input).unwrap().into_value().unwrap()
+    }
+
+    #[test]
+    fn variables_from_a_file_stay_defined()
+    {
+        let mut calculator = Calculator::new();
+        let results = calculator.eval_lines("# a worksheet\nwidth = 3\n\nheight = width * 2\narea(w, h) = w * h\n");
+        assert_eq!(results, vec![
+            Ok(StatementResult::Assignment(String::from("width"), Value::Int(3))),
+            Ok(StatementResult::Assignment(String::from("height"), Value::Int(6))),
+            Ok(StatementResult::Definition(String::from("area"))),
+        ]);
+
+        assert_eq!(value(&mut calculator, "area(width, height)"), Value::Int(18));
+        assert_eq!(calculator.eval_lines("1 / 0\nwidth"), vec![Err(CalcError::DivisionByZero), Ok(StatementResult::Expression(Value::Int(3)))]);
+    }
+
+    #[test]
+    fn definitions_load_past_a_broken_line()
+    {
+        let mut calculator = Calculator::new();
+        let errors = calculator.load_definitions("  # startup\nrate = 2\ndouble(x) = x * rate +\n\ntriple(x) = x * 3\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 3);
+        assert_eq!(errors[0].1, CalcError::MissingOperand(String::from("+")));
+
+        assert_eq!(value(&mut calculator, "triple(rate)"), Value::Int(6));
+        assert!(calculator.eval("double(1)").is_err());
+        assert_eq!(calculator.history().len(), 1);
+    }
+
+    #[test]
+    fn memory_accumulates_results()
+    {
+        let mut calculator = Calculator::new();
+        assert_eq!(calculator.memory_add(), Err(CalcError::NoPreviousResult));
+        calculator.eval("5").unwrap();
+        assert_eq!(calculator.memory_add(), Ok(&Value::Int(5)));
+        calculator.eval("3").unwrap();
+        assert_eq!(calculator.memory_add(), Ok(&Value::Int(8)));
+        assert_eq!(value(&mut calculator, "mem * 2"), Value::Int(16));
+        assert_eq!(calculator.memory_subtract(), Ok(&Value::Int(-8)));
+        calculator.memory_clear();
+        assert_eq!(calculator.memory(), &Value::Int(0));
+    }
+
+    #[test]
+    fn builder_settings_take_effect()
+    {
+        let mut calculator = Calculator::builder().base(Base::Hex).angle(Angle::Degrees).precision(3).build();
+        let mut shown = |input: &str| {
+            let result = calculator.eval(input).unwrap();
+            calculator.format(&result)
+        };
+        assert_eq!(shown("255"), "0xff");
+        assert_eq!(shown("sin(90)"), "1.000");
+        assert_eq!(shown("1 / 3.0"), "0.333");
+    }
+
+    #[test]
+    fn native_functions_get_the_arguments_they_declared()
+    {
+        let mut calculator = Calculator::new();
+        calculator.register_function("double", Arity::Exactly(1), Box::new(|args| Ok(args[0] * 2.0)));
+        calculator.register_function("total", Arity::AtLeast(1), Box::new(|args| Ok(args.iter().sum())));
+
+        assert_eq!(value(&mut calculator, "double(21)"), Value::Float(42.0));
+        assert_eq!(value(&mut calculator, "total(1, 2, 3.5)"), Value::Float(6.5));
+        assert_eq!(calculator.eval("double()"), Err(CalcError::WrongArgumentCount{ name: String::from("double"), expected: Arity::Exactly(1), found: 0 }));
+        assert_eq!(calculator.eval("total()"), Err(CalcError::WrongArgumentCount{ name: String::from("total"), expected: Arity::AtLeast(1), found: 0 }));
+    }
+
+    #[test]
+    fn reset_forgets_the_session_but_keeps_native_functions()
+    {
+        let mut calculator = Calculator::builder().precision(2).operator_alias("x", Token::Mul).build();
+        calculator.register_function("double", Arity::Exactly(1), Box::new(|args| Ok(args[0] * 2.0)));
+        assert_eq!(value(&mut calculator, "3 x 4"), Value::Int(12));
+        calculator.eval("x = 5").unwrap();
+        calculator.eval("square(y) = y * y").unwrap();
+        calculator.memory_add().unwrap();
+        calculator.seed(3);
+
+        calculator.reset();
+        assert_eq!(calculator.variable("x"), None);
+        assert_eq!(calculator.eval("square(2)"), Err(CalcError::UnknownFunction(String::from("square"))));
+        assert!(calculator.history().is_empty());
+        assert_eq!(calculator.memory(), &Value::Int(0));
+        assert_eq!(calculator.options().precision, Some(2));
+        assert_eq!(calculator.options().seed, None);
+        assert_eq!(value(&mut calculator, "double(2)"), Value::Float(4.0));
+        //`x` is only a name again.
+        assert!(calculator.options().operator_aliases.is_empty());
+        assert!(matches!(calculator.eval("3 x 4"), Err(CalcError::UnexpectedToken{ .. })));
+    }
+
+    #[test]
+    fn tokens_are_traced_to_the_given_writer()
+    {
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let mut calculator = Calculator::builder().trace_to(trace.clone()).build();
+        calculator.eval("1 + 2").unwrap();
+        assert_eq!(String::from_utf8(trace.borrow().clone()).unwrap(),
+            "lexer: Integer(1) at 0\nlexer: Plus at 2\nlexer: Integer(2) at 4\nlexer: Eof at 5\n");
+
+        trace.borrow_mut().clear();
+        calculator.reset();
+        calculator.eval("x").unwrap_err();
+        assert_eq!(String::from_utf8(trace.borrow().clone()).unwrap(), "lexer: Identifier(\"x\") at 0\nlexer: Eof at 1\n");
+    }
+
+    #[test]
+    fn statements_are_reported_in_order()
+    {
+        let mut calculator = Calculator::new();
+        let mut seen = Vec::new();
+        calculator.eval_statements("1;2;3", |statement, result| seen.push((String::from(statement), result)));
+        assert_eq!(seen, vec![
+            (String::from("1"), Ok(StatementResult::Expression(Value::Int(1)))),
+            (String::from("2"), Ok(StatementResult::Expression(Value::Int(2)))),
+            (String::from("3"), Ok(StatementResult::Expression(Value::Int(3)))),
+        ]);
+        assert_eq!(calculator.history().len(), 3);
+    }
+
+    #[test]
+    fn a_broken_statement_doesnt_stop_the_rest()
+    {
+        let mut calculator = Calculator::new();
+        let mut results = Vec::new();
+        calculator.eval_statements("1+1; bad+; 2+2", |statement, result| results.push((String::from(statement), result.map(|result| result.into_value()))));
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], (String::from("1+1"), Ok(Some(Value::Int(2)))));
+        assert_eq!(results[1].0, "bad+");
+        assert!(matches!(results[1].1, Err(CalcError::UnexpectedToken{ .. })));
+        assert_eq!(results[2], (String::from("2+2"), Ok(Some(Value::Int(4)))));
+    }
+
+    #[test]
+    fn at_is_the_previous_statement_on_the_line()
+    {
+        let mut calculator = Calculator::new();
+        let mut values = Vec::new();
+        calculator.eval_statements("5; @+3; @*2", |_statement, result| values.push(result.unwrap().into_value()));
+        assert_eq!(values, vec![Some(Value::Int(5)), Some(Value::Int(8)), Some(Value::Int(16))]);
+        //It doesn't carry over to the next line.
+        assert_eq!(calculator.eval("@"), Err(CalcError::NoPreviousResult));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::environment::Environment;
+use crate::error::CalcError;
+use crate::interpreter::Interpreter;
+use crate::lexer::Lexer;
+use crate::options::Options;
+use crate::parser::Parser;
+use crate::value::Value;
+
+//Format a number of seconds as `H:MM:SS`, e.g. 8100 as `2:15:00`.
+pub fn format_duration(seconds: i64) -> String
+{
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+    format!("{}{}:{:02}:{:02}", sign, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+//Evaluate arithmetic over time literals and print the result as a time.
+//
+//        Each `H:MM:SS` literal stands for its number of seconds, so
+//        `1:30:00 + 0:45:00` gives `2:15:00` and `0:20:00 * 3` gives
+//        `1:00:00`. Results that aren't whole seconds are rounded.
+pub fn evaluate_time(input: &str) -> Result<String, CalcError>
+{
+    let options = Options::default();
+    options.check_input(input)?;
+
+    let mut lexer = Lexer::create_time_lexer(String::from(input));
+    let node = Parser::create_parser(&mut lexer)?.parse()?;
+    let mut env = Environment::create_environment();
+    let value = Interpreter::create_interpreter(&mut env, &options).visit(&node)?;
+
+    match value
+    {
+        Value::Int(seconds) => Ok(format_duration(seconds)),
+        Value::Float(seconds) if seconds.is_finite() && seconds.abs() < i64::MAX as f64 => Ok(format_duration(seconds.round() as i64)),
+        Value::Tuple(_) => Err(CalcError::TypeError(String::from("expected a time, found a tuple"))),
+        Value::Bool(_) => Err(CalcError::TypeError(String::from("expected a time, found a boolean"))),
+        _ => Err(CalcError::Overflow),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::CalcError;
+
+    use super::evaluate_time;
+
+    #[test]
+    fn times_add_and_subtract()
+    {
+        assert_eq!(evaluate_time("1:30:00 + 0:45:00"), Ok(String::from("2:15:00")));
+        assert_eq!(evaluate_time("0:00:30 + 0:00:45"), Ok(String::from("0:01:15")));
+        assert_eq!(evaluate_time("1:00:00 - 0:00:01"), Ok(String::from("0:59:59")));
+        assert_eq!(evaluate_time("0:10:00 - 0:20:00"), Ok(String::from("-0:10:00")));
+        assert_eq!(evaluate_time("0:20:00 * 3"), Ok(String::from("1:00:00")));
+        assert_eq!(evaluate_time("1:60:00"), Err(CalcError::InvalidTimeLiteral(String::from("1:60:00"))));
+    }
+}
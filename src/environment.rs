@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::ast::Ast;
+use crate::error::CalcError;
+use crate::functions::Arity;
+use crate::random::Rng;
+use crate::value::Value;
+
+//A function defined by the user, e.g. `square(x) = x * x`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct UserFunction
+{
+    pub params: Vec<String>,
+    pub body: Ast
+}
+
+//A function supplied by the program embedding the calculator, see
+//`Calculator::register_function`. It gets its arguments as floats.
+pub type NativeFunction = Box<dyn Fn(&[f64]) -> Result<f64, CalcError>>;
+
+//Everything an evaluation can read or define: global variables,
+//user-defined and native functions, the memory register (readable as `mem`) and
+//the generator behind `random()` and `randint()`.
+pub struct Environment
+{
+    pub variables: HashMap<String, Value>,
+    pub functions: HashMap<String, UserFunction>,
+    pub natives: HashMap<String, (Arity, NativeFunction)>,
+    pub memory: Value,
+    //The last value produced by the statements on the current line,
+    //readable as `@`.
+    pub previous: Option<Value>,
+    pub rng: Rng
+}
+
+impl Environment
+{
+    pub fn create_environment() -> Environment
+    {
+        Environment{
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+            natives: HashMap::new(),
+            memory: Value::Int(0),
+            previous: None,
+            rng: Rng::from_clock(),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::functions::Arity;
+use crate::token::Token;
+
+//Everything that can go wrong lexing, parsing or evaluating an input.
+//More kinds may be added, so a `match` outside this crate needs a `_` arm.
+#[derive(PartialEq, Debug)]
+#[non_exhaustive]
+pub enum CalcError
+{
+    //The offending character and its (zero-based) position in the input.
+    UnexpectedChar(char, usize),
+    //A token the grammar doesn't allow here, with what it expected instead
+    //and the last few tokens before it (`3 +` for `3 + )`).
+    UnexpectedToken { found: Token, expected: String, after: String },
+    EmptyExpression,
+    //The input was longer than the configured limit (in characters).
+    InputTooLong(usize),
+    //The expression nests deeper than the parser allows.
+    TooDeeplyNested(usize),
+    //A number literal that can't be read, such as `0x1p` (no exponent digits).
+    MalformedNumber(String),
+    //A time literal that isn't `H:MM:SS` with minutes and seconds below 60.
+    InvalidTimeLiteral(String),
+    EmptyParentheses,
+    //The input ended right after this operator.
+    MissingOperand(String),
+    //An opening or closing delimiter without a partner, and its position.
+    UnmatchedDelimiter(char, usize),
+    DivisionByZero,
+    //An integer division with a remainder, in strict mode.
+    InexactDivision(i64, i64),
+    Overflow,
+    DomainError(String),
+    UnknownFunction(String),
+    UnknownVariable(String),
+    WrongArgumentCount { name: String, expected: Arity, found: usize },
+    TypeError(String),
+    InvalidAssignmentTarget,
+    NoPreviousResult,
+    RecursionLimit,
+    //The evaluation ran longer than the configured time limit.
+    Timeout(Duration),
+    //`assert` or `assert_eq` failed; the detail may be empty.
+    AssertionFailed(String),
+    InvalidSnapshot(String),
+    //A `Template` was filled with fewer values than its placeholders
+    //need; the lowest placeholder it uses that has no value.
+    MissingPlaceholder(usize),
+    //An RPN operator or function found fewer values on the stack than it
+    //takes.
+    StackUnderflow(String),
+    //RPN input left this many values on the stack instead of one.
+    LeftoverOperands(usize)
+}
+
+impl fmt::Display for CalcError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            CalcError::UnexpectedChar(c, pos) => write!(f, "unexpected character '{}' at position {}", c, pos),
+            CalcError::UnexpectedToken { found, expected, after } => {
+                if !after.is_empty(){
+                    write!(f, "after '{}', ", after)?;
+                }
+                match found
+                {
+                    Token::Eof => write!(f, "expected {} but found {}", expected, found),
+                    found => write!(f, "expected {} but found '{}'", expected, found),
+                }
+            }
+            CalcError::EmptyExpression => write!(f, "empty expression"),
+            CalcError::InputTooLong(limit) => write!(f, "input is longer than the limit of {} characters", limit),
+            CalcError::TooDeeplyNested(limit) => write!(f, "expression is nested more than {} levels deep", limit),
+            CalcError::MalformedNumber(literal) => write!(f, "malformed number '{}'", literal),
+            CalcError::InvalidTimeLiteral(literal) => write!(f, "invalid time '{}', expected H:MM:SS", literal),
+            CalcError::EmptyParentheses => write!(f, "empty parentheses"),
+            CalcError::MissingOperand(operator) => write!(f, "expected an operand after '{}'", operator),
+            CalcError::UnmatchedDelimiter(c, pos) => write!(f, "unmatched '{}' at position {}", c, pos),
+            CalcError::DivisionByZero => write!(f, "division by zero"),
+            CalcError::InexactDivision(left, right) =>
+                write!(f, "non-exact integer division {} / {}; use a float operand such as {}.0 / {}", left, right, left, right),
+            CalcError::Overflow => write!(f, "overflow"),
+            CalcError::DomainError(message) => write!(f, "domain error: {}", message),
+            CalcError::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            CalcError::UnknownVariable(name) => write!(f, "unknown variable '{}'", name),
+            CalcError::WrongArgumentCount { name, expected, found } =>
+                write!(f, "{} expects {}, got {}", name, expected, found),
+            CalcError::TypeError(message) => write!(f, "type error: {}", message),
+            CalcError::InvalidAssignmentTarget => write!(f, "only a variable name can be assigned to"),
+            CalcError::NoPreviousResult => write!(f, "there is no previous result yet"),
+            CalcError::RecursionLimit => write!(f, "too many nested function calls"),
+            CalcError::Timeout(limit) => write!(f, "evaluation took longer than the limit of {:?}", limit),
+            CalcError::AssertionFailed(detail) if detail.is_empty() => write!(f, "assertion failed"),
+            CalcError::AssertionFailed(detail) => write!(f, "assertion failed: {}", detail),
+            CalcError::InvalidSnapshot(message) => write!(f, "invalid snapshot: {}", message),
+            CalcError::MissingPlaceholder(index) => write!(f, "no value for placeholder ${}", index),
+            CalcError::StackUnderflow(operator) => write!(f, "not enough values on the stack for '{}'", operator),
+            CalcError::LeftoverOperands(count) => write!(f, "{} values left on the stack, expected a single result", count),
+        }
+    }
+}
+
+impl std::error::Error for CalcError {}
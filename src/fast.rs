@@ -0,0 +1,327 @@
+use std::collections::VecDeque;
+
+use crate::error::CalcError;
+use crate::functions::{call_function, check_arity, constant, find_builtin};
+use crate::interpreter::{binary_op, logical_op, Interpreter};
+use crate::lexer::Lexer;
+use crate::options::{Angle, Options};
+use crate::parser::{remember, unexpected, MAX_DEPTH};
+use crate::random::Rng;
+use crate::token::Token;
+use crate::value::Value;
+
+//The outcome of evaluating a subexpression. Evaluation errors are carried
+//along instead of returned straight away, so a later syntax error is still
+//reported first, as it would be by `parse` followed by evaluation.
+type Evaluated = Result<Value, CalcError>;
+
+//Single-pass evaluator: the same grammar as the parser, but each rule
+//computes its value as soon as it is recognized instead of building an
+//`Ast` node.
+struct FastEvaluator
+{
+    lexer: Lexer,
+    current_token: Token,
+    recent: VecDeque<Token>,
+    nesting: usize,
+    rng: Rng,
+    //The first call to a built-in with the wrong number of arguments, in
+    //the order `parse` checks them (a call before its arguments).
+    arity_error: Option<CalcError>
+}
+
+impl FastEvaluator
+{
+    fn eat(&mut self) -> Result<(), CalcError>
+    {
+        let next_token = self.lexer.get_next_token()?;
+        remember(&mut self.recent, std::mem::replace(&mut self.current_token, next_token));
+        Ok(())
+    }
+
+    fn unexpected(&self, expected: &str) -> CalcError
+    {
+        unexpected(&self.current_token, expected, &self.recent)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), CalcError>
+    {
+        if self.current_token != expected{
+            return Err(self.unexpected(&format!("'{}'", expected.to_str())));
+        }
+        self.eat()
+    }
+
+    //call : IDENTIFIER LPAREN (disjunction (COMMA disjunction)*)? RPAREN
+    fn call(&mut self, name: String) -> Result<Evaluated, CalcError>
+    {
+        if find_builtin(&name).is_some_and(|builtin| builtin.lazy){
+            return Err(CalcError::TypeError(format!("{} is not supported by evaluate_fast", name)));
+        }
+        self.expect(Token::Lparen)?;
+
+        let earlier = self.arity_error.take();
+        let mut args = Vec::new();
+        if self.current_token != Token::Rparen{
+            args.push(self.disjunction()?);
+            while self.current_token == Token::Comma{
+                self.eat()?;
+                args.push(self.disjunction()?);
+            }
+        }
+        self.expect(Token::Rparen)?;
+
+        let own = find_builtin(&name).and_then(|builtin| check_arity(builtin.name, builtin.arity, args.len()).err());
+        let inner = self.arity_error.take();
+        self.arity_error = earlier.or(own).or(inner);
+
+        Ok(args.into_iter().collect::<Result<Vec<Value>, CalcError>>()
+            .and_then(|args| call_function(&name, args, Angle::Radians, &mut self.rng)))
+    }
+
+    //atom : NUMBER | BOOL | call | IDENTIFIER | AT | PLACEHOLDER | conditional | LPAREN disjunction RPAREN
+    fn atom(&mut self) -> Result<Evaluated, CalcError>
+    {
+        match self.current_token.clone()
+        {
+            Token::Integer(integer) => {self.eat()?; Ok(Ok(Value::Int(integer)))},
+            Token::Float(float) => {self.eat()?; Ok(Ok(Value::Float(float)))},
+            Token::Bool(boolean) => {self.eat()?; Ok(Ok(Value::Bool(boolean)))},
+            Token::Identifier(name) => {
+                self.eat()?;
+                if self.current_token == Token::Lparen{
+                    return self.call(name);
+                }
+                Ok(constant(&name).ok_or(CalcError::UnknownVariable(name)))
+            }
+            //Both branches are parsed, but the value and errors of the one
+            //not taken are dropped.
+            Token::If => {
+                self.eat()?;
+                let condition = self.disjunction()?;
+                self.expect(Token::Then)?;
+                let then = self.disjunction()?;
+                self.expect(Token::Else)?;
+                let otherwise = self.disjunction()?;
+                Ok(condition.and_then(|condition| if condition.as_bool()? { then } else { otherwise }))
+            }
+            Token::Placeholder(index) => {self.eat()?; Ok(Err(CalcError::UnknownVariable(format!("${}", index))))},
+            //There is no previous statement in a single expression.
+            Token::At => {self.eat()?; Ok(Err(CalcError::NoPreviousResult))},
+            Token::Lparen => {
+                self.eat()?;
+                if self.current_token == Token::Rparen{
+                    return Err(CalcError::EmptyParentheses);
+                }
+                let value = self.disjunction()?;
+                self.expect(Token::Rparen)?;
+                Ok(value)
+            }
+            Token::Eof => match self.recent.back()
+            {
+                Some(operator) => Err(CalcError::MissingOperand(operator.to_str())),
+                None => Err(CalcError::EmptyExpression),
+            },
+            _ => Err(self.unexpected("a number, a name or '('"))
+        }
+    }
+
+    //percent : atom PERCENT*
+    fn percent(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let mut value = self.atom()?;
+
+        while self.current_token == Token::Percent{
+            self.eat()?;
+            value = value.and_then(|operand| Interpreter::unary_op(&Token::Percent, operand));
+        }
+
+        Ok(value)
+    }
+
+    //power : percent (POWER factor)?
+    fn power(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let base = self.percent()?;
+
+        if self.current_token == Token::Power{
+            self.eat()?;
+            let exponent = self.factor()?;
+            return Ok(combine(&Token::Power, base, exponent));
+        }
+
+        Ok(base)
+    }
+
+    //factor : (PLUS | MINUS | NOT) factor | power
+    fn factor(&mut self) -> Result<Evaluated, CalcError>
+    {
+        if self.nesting >= MAX_DEPTH{
+            return Err(CalcError::TooDeeplyNested(MAX_DEPTH));
+        }
+        self.nesting += 1;
+        let value = self.signed_factor();
+        self.nesting -= 1;
+        value
+    }
+
+    fn signed_factor(&mut self) -> Result<Evaluated, CalcError>
+    {
+        match self.current_token
+        {
+            Token::Plus | Token::Minus | Token::Not => {
+                let operator = self.current_token.clone();
+                self.eat()?;
+                Ok(self.factor()?.and_then(|operand| Interpreter::unary_op(&operator, operand)))
+            }
+            _ => self.power()
+        }
+    }
+
+    //term : factor ((MUL | DIV | OF) factor)*
+    fn term(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let mut value = self.factor()?;
+
+        while self.current_token == Token::Mul || self.current_token == Token::Div || self.current_token == Token::Of{
+            let operator = self.current_token.clone();
+            self.eat()?;
+            let right = self.factor()?;
+            value = combine(&operator, value, right);
+        }
+
+        Ok(value)
+    }
+
+    //expr : term ((PLUS | MINUS) term)*
+    fn expr(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let mut value = self.term()?;
+
+        while self.current_token == Token::Plus || self.current_token == Token::Minus{
+            let operator = self.current_token.clone();
+            self.eat()?;
+            let right = self.term()?;
+            value = combine(&operator, value, right);
+        }
+
+        Ok(value)
+    }
+
+    //comparison : expr (comparison_operator expr)?
+    fn comparison(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let value = self.expr()?;
+
+        if self.current_token.is_comparison(){
+            let operator = self.current_token.clone();
+            self.eat()?;
+            let right = self.expr()?;
+            return Ok(combine(&operator, value, right));
+        }
+
+        Ok(value)
+    }
+
+    //conjunction : comparison (AND comparison)*
+    fn conjunction(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let mut value = self.comparison()?;
+
+        while self.current_token == Token::And{
+            self.eat()?;
+            let right = self.comparison()?;
+            value = short_circuit(&Token::And, value, right);
+        }
+
+        Ok(value)
+    }
+
+    //disjunction : conjunction (OR conjunction)*
+    fn disjunction(&mut self) -> Result<Evaluated, CalcError>
+    {
+        let mut value = self.conjunction()?;
+
+        while self.current_token == Token::Or{
+            self.eat()?;
+            let right = self.conjunction()?;
+            value = short_circuit(&Token::Or, value, right);
+        }
+
+        Ok(value)
+    }
+}
+
+//Apply `&&` or `||`. The right side has been computed already, but as in
+//`evaluate` its failure only counts when the left side doesn't decide
+//the result.
+fn short_circuit(operator: &Token, left: Evaluated, right: Evaluated) -> Evaluated
+{
+    logical_op(operator, &left?, || right)
+}
+
+//Apply a binary operator unless either side has already failed, keeping
+//the leftmost failure.
+fn combine(operator: &Token, left: Evaluated, right: Evaluated) -> Evaluated
+{
+    binary_op(operator, &left?, &right?)
+}
+
+//Evaluate `input` without building a syntax tree, for callers that only
+//want the value of many simple expressions.
+//
+//        Numbers, booleans, operators (including comparisons and logical
+//        operators), parentheses, the built-in constants and the built-in
+//        functions are supported, and give the same value (or error) as
+//        `evaluate`. Assignments, function
+//        definitions, special forms such as `deriv` and any other variables
+//        are not (so `x = 1` is an unexpected `=` and `x` is an unknown
+//        variable). Long operator chains are evaluated in a loop, so unlike
+//        `evaluate` they are not limited in length.
+pub fn evaluate_fast(input: &str) -> Result<Value, CalcError>
+{
+    Options::default().check_input(input)?;
+
+    let mut lexer = Lexer::create_lexer(String::from(input));
+    let current_token = lexer.get_next_token()?;
+    let mut evaluator = FastEvaluator{
+        lexer,
+        current_token,
+        recent: VecDeque::new(),
+        nesting: 0,
+        rng: Rng::from_clock(),
+        arity_error: None,
+    };
+
+    let value = evaluator.disjunction()?;
+    if evaluator.current_token != Token::Eof{
+        return Err(evaluator.unexpected("an operator or the end of the input"));
+    }
+    if let Some(e) = evaluator.arity_error{
+        return Err(e);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::evaluate;
+
+    use super::evaluate_fast;
+
+    #[test]
+    fn agrees_with_evaluate()
+    {
+        let inputs = [
+            "2 + 3 * 4", "(2 + 3) * 4", "2 ^ 3 ^ 2", "-2 ^ 2", "7 / 2", "7 / 2.0", "10 % 3",
+            "50%", "10% of 200", "1 < 2 && 3 >= 3", "!(1 == 1) || false", "true && 1 / 0 > 0",
+            "false && 1 / 0 > 0", "sqrt(16) + max(1, 5, 3)", "copysign(pi, -1) + pi", "divmod(7, 2)",
+            "if 1 < 2 then 10 else 1 / 0", "1 / 0", "1 + true", "2 * (3", "max()", "x + 1", ")",
+            "1 / 0 + sqrt(1, 2)", "sqrt(1, 2) + 1 / 0", "max(sqrt(), 1 / 0)",
+        ];
+        for input in inputs{
+            assert_eq!(evaluate_fast(input), evaluate(input).map(|outcome| outcome.value), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,469 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::error::CalcError;
+#[cfg(feature = "bignum")]
+use crate::interpreter::as_bigint;
+use crate::interpreter::{binary_op, compare_values};
+use crate::options::Angle;
+use crate::random::Rng;
+use crate::token::Token;
+use crate::value::Value;
+
+//Integer division shared by the `/` operator and `divmod`.
+//
+//        The quotient is truncated toward zero, so the remainder takes
+//        the sign of the dividend and `a == q * b + r` always holds:
+//        divmod(7, 2) is (3, 1) and divmod(-7, 2) is (-3, -1).
+pub fn checked_divmod(dividend: i64, divisor: i64) -> Result<(i64, i64), CalcError>
+{
+    if divisor == 0{
+        return Err(CalcError::DivisionByZero);
+    }
+
+    match (dividend.checked_div(divisor), dividend.checked_rem(divisor))
+    {
+        (Some(quotient), Some(remainder)) => Ok((quotient, remainder)),
+        _ => Err(CalcError::Overflow),
+    }
+}
+
+//The built-in constants, by name.
+pub const CONSTANTS: &[(&str, f64)] = &[
+    ("e", std::f64::consts::E),
+    ("pi", std::f64::consts::PI),
+];
+
+//Resolve a bare identifier to one of the built-in constants.
+pub fn constant(name: &str) -> Option<Value>
+{
+    CONSTANTS.iter().find(|(constant, _)| *constant == name).map(|(_, value)| Value::Float(*value))
+}
+
+//How many arguments a built-in or native function takes.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Arity
+{
+    Exactly(usize),
+    AtLeast(usize)
+}
+
+//`1 argument`, `2 arguments`, `at least 1 argument`.
+impl fmt::Display for Arity
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let (prefix, count) = match self
+        {
+            Arity::Exactly(count) => ("", *count),
+            Arity::AtLeast(count) => ("at least ", *count),
+        };
+        write!(f, "{}{} argument{}", prefix, count, if count == 1 { "" } else { "s" })
+    }
+}
+
+//A built-in function, as listed by `:help`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Builtin
+{
+    pub name: &'static str,
+    //Parameter names, for display: `divmod(a, b)`.
+    pub params: &'static str,
+    pub arity: Arity,
+    pub description: &'static str,
+    //Special forms such as `deriv` receive their arguments unevaluated and
+    //are run by the interpreter rather than `call_function`.
+    pub lazy: bool
+}
+
+const fn builtin(name: &'static str, params: &'static str, arity: Arity, description: &'static str) -> Builtin
+{
+    Builtin{ name, params, arity, description, lazy: false }
+}
+
+const fn special_form(name: &'static str, params: &'static str, arity: Arity, description: &'static str) -> Builtin
+{
+    Builtin{ name, params, arity, description, lazy: true }
+}
+
+//Every built-in function. `call_function` checks calls against this
+//table before dispatching, so a function must be listed here to exist.
+pub const BUILTINS: &[Builtin] = &[
+    builtin("sqrt", "x", Arity::Exactly(1), "square root"),
+    builtin("pow", "x, y", Arity::Exactly(2), "x raised to the power y, like x ^ y"),
+    builtin("exp", "x", Arity::Exactly(1), "e raised to the power x"),
+    builtin("ln", "x", Arity::Exactly(1), "natural logarithm"),
+    builtin("log", "x", Arity::Exactly(1), "base 10 logarithm"),
+    builtin("log2", "x", Arity::Exactly(1), "base 2 logarithm"),
+    builtin("divmod", "a, b", Arity::Exactly(2), "quotient and remainder of integer division"),
+    builtin("signum", "x", Arity::Exactly(1), "-1, 0 or 1 according to the sign of x"),
+    builtin("copysign", "x, y", Arity::Exactly(2), "the magnitude of x with the sign of y"),
+    builtin("int", "x", Arity::Exactly(1), "x as an integer: 1 or 0 for a boolean, truncated toward zero for a float"),
+    builtin("bool", "x", Arity::Exactly(1), "x as a boolean: false for zero, true for any other number"),
+    builtin("max", "x, ...", Arity::AtLeast(1), "largest argument"),
+    builtin("min", "x, ...", Arity::AtLeast(1), "smallest argument"),
+    builtin("sin", "x", Arity::Exactly(1), "sine"),
+    builtin("cos", "x", Arity::Exactly(1), "cosine"),
+    builtin("tan", "x", Arity::Exactly(1), "tangent"),
+    builtin("asin", "x", Arity::Exactly(1), "inverse sine"),
+    builtin("acos", "x", Arity::Exactly(1), "inverse cosine"),
+    builtin("atan", "x", Arity::Exactly(1), "inverse tangent"),
+    builtin("atan2", "y, x", Arity::Exactly(2), "angle of the point (x, y)"),
+    special_form("sum", "i, from, to, expr", Arity::Exactly(4), "sum of expr for i = from, from + 1, ..., to"),
+    special_form("product", "i, from, to, expr", Arity::Exactly(4), "product of expr for i = from, from + 1, ..., to"),
+    special_form("deriv", "expr, x, point", Arity::Exactly(3), "numerical derivative of expr with respect to x at point"),
+    builtin("assert", "condition", Arity::Exactly(1), "fail unless condition is true (e.g. assert(2 + 2 == 4))"),
+    builtin("assert_eq", "a, b", Arity::Exactly(2), "fail unless a == b"),
+    builtin("random", "", Arity::Exactly(0), "random float in [0, 1)"),
+    builtin("randint", "a, b", Arity::Exactly(2), "random integer between a and b inclusive"),
+];
+
+//Look up a built-in function by name.
+pub fn find_builtin(name: &str) -> Option<&'static Builtin>
+{
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+//Check that the function `name`, taking `arity` arguments, was called
+//with `found` of them.
+pub fn check_arity(name: &str, arity: Arity, found: usize) -> Result<(), CalcError>
+{
+    let accepted = match arity
+    {
+        Arity::Exactly(expected) => found == expected,
+        Arity::AtLeast(minimum) => found >= minimum,
+    };
+    if !accepted{
+        return Err(CalcError::WrongArgumentCount { name: String::from(name), expected: arity, found });
+    }
+    Ok(())
+}
+
+//Logarithm of `x` in the given base, rejecting non-positive arguments.
+fn logarithm(name: &str, x: f64, base: f64) -> Result<Value, CalcError>
+{
+    if x <= 0.0{
+        return Err(CalcError::DomainError(format!("{} is undefined for {}", name, x)));
+    }
+
+    let result = if base == std::f64::consts::E{
+        x.ln()
+    } else if base == 2.0{
+        x.log2()
+    } else {
+        x.log10()
+    };
+    Ok(Value::Float(result))
+}
+
+//Convert a trigonometric argument in `angle` units to radians.
+fn to_radians(x: f64, angle: Angle) -> f64
+{
+    match angle
+    {
+        Angle::Radians => x,
+        Angle::Degrees => x.to_radians(),
+    }
+}
+
+//Convert an inverse trigonometric result in radians to `angle` units.
+fn from_radians(x: f64, angle: Angle) -> Value
+{
+    match angle
+    {
+        Angle::Radians => Value::Float(x),
+        Angle::Degrees => Value::Float(x.to_degrees()),
+    }
+}
+
+//Inverse sine or cosine of `x`, which must lie in [-1, 1].
+fn inverse_trig(name: &str, x: f64, angle: Angle) -> Result<Value, CalcError>
+{
+    if !(-1.0..=1.0).contains(&x){
+        return Err(CalcError::DomainError(format!("{} is undefined for {}", name, x)));
+    }
+
+    let result = if name == "asin"{
+        x.asin()
+    } else {
+        x.acos()
+    };
+    Ok(from_radians(result, angle))
+}
+
+//`divmod(a, b)` as a tuple. Operands or results that don't fit in an
+//i64 are an overflow, or exact with the `bignum` feature.
+fn divmod(a: &Value, b: &Value) -> Result<Value, CalcError>
+{
+    let (quotient, remainder) = match a.as_int().and_then(|a| checked_divmod(a, b.as_int()?))
+    {
+        Ok((quotient, remainder)) => (Value::Int(quotient), Value::Int(remainder)),
+        Err(CalcError::Overflow) => big_divmod(a, b)?,
+        Err(e) => return Err(e),
+    };
+    Ok(Value::Tuple(vec![quotient, remainder]))
+}
+
+#[cfg(not(feature = "bignum"))]
+fn big_divmod(_a: &Value, _b: &Value) -> Result<(Value, Value), CalcError>
+{
+    Err(CalcError::Overflow)
+}
+
+#[cfg(feature = "bignum")]
+fn big_divmod(a: &Value, b: &Value) -> Result<(Value, Value), CalcError>
+{
+    match (as_bigint(a), as_bigint(b))
+    {
+        (Some(a), Some(b)) => {
+            let (quotient, remainder) = a.divmod(&b)?;
+            Ok((Value::from_bigint(quotient), Value::from_bigint(remainder)))
+        }
+        _ => Err(CalcError::TypeError(String::from("divmod expects integers"))),
+    }
+}
+
+//Order two numbers, comparing integers exactly and anything else as floats.
+fn compare(a: &Value, b: &Value) -> Result<Ordering, CalcError>
+{
+    match (a, b)
+    {
+        (Value::Int(a), Value::Int(b)) => Ok(a.cmp(b)),
+        _ => Ok(a.as_float()?.partial_cmp(&b.as_float()?).unwrap_or(Ordering::Equal)),
+    }
+}
+
+//The largest (`Ordering::Greater`) or smallest (`Ordering::Less`) of one
+//or more arguments, returned unchanged so `max(1, 2)` stays an integer.
+fn extremum(args: Vec<Value>, wanted: Ordering) -> Result<Value, CalcError>
+{
+    let mut args = args.into_iter();
+    let mut best = args.next().expect("the registry requires at least one argument");
+    best.as_float()?;
+    for arg in args{
+        if compare(&arg, &best)? == wanted{
+            best = arg;
+        }
+    }
+    Ok(best)
+}
+
+//-1, 0 or 1 according to the sign of `x`, as an integer unless `x` is a
+//float. Unlike `f64::signum`, zero maps to zero.
+fn signum(x: &Value) -> Result<Value, CalcError>
+{
+    match x
+    {
+        Value::Int(integer) => Ok(Value::Int(integer.signum())),
+        Value::Float(float) if *float == 0.0 => Ok(Value::Float(0.0)),
+        Value::Float(float) => Ok(Value::Float(float.signum())),
+        other => Ok(Value::Int(other.as_float()?.signum() as i64)),
+    }
+}
+
+//The magnitude of `x` with the sign of `y`, staying an integer when both are.
+fn copysign(x: &Value, y: &Value) -> Result<Value, CalcError>
+{
+    match (x, y)
+    {
+        (Value::Int(x), Value::Int(y)) if (*x < 0) == (*y < 0) => Ok(Value::Int(*x)),
+        (Value::Int(x), Value::Int(_)) => x.checked_neg().map(Value::Int).ok_or(CalcError::Overflow),
+        _ => Ok(Value::Float(x.as_float()?.copysign(y.as_float()?))),
+    }
+}
+
+//Convert a boolean or a number to an integer, the explicit way to do
+//arithmetic on a comparison: int(3 < 5) + 1 is 2.
+fn to_int(x: &Value) -> Result<Value, CalcError>
+{
+    match x
+    {
+        Value::Bool(boolean) => Ok(Value::Int(*boolean as i64)),
+        Value::Float(float) if float.is_nan() => Err(CalcError::DomainError(String::from("int is undefined for NaN"))),
+        //The bounds are -2^63 and 2^63, which convert exactly.
+        Value::Float(float) if *float >= -(i64::MIN as f64) || *float < i64::MIN as f64 => Err(CalcError::Overflow),
+        Value::Float(float) => Ok(Value::Int(*float as i64)),
+        other => {other.as_float()?; Ok(other.clone())},
+    }
+}
+
+//Convert a number (or a boolean) to a boolean: zero is false.
+fn to_bool(x: &Value) -> Result<Value, CalcError>
+{
+    match x
+    {
+        Value::Bool(boolean) => Ok(Value::Bool(*boolean)),
+        other => Ok(Value::Bool(other.as_float()? != 0.0)),
+    }
+}
+
+//Dispatch a call to one of the built-in functions. Trigonometric
+//functions work in `angle` units; `random` and `randint` draw from `rng`.
+pub fn call_function(name: &str, args: Vec<Value>, angle: Angle, rng: &mut Rng) -> Result<Value, CalcError>
+{
+    let builtin = find_builtin(name).ok_or_else(|| CalcError::UnknownFunction(String::from(name)))?;
+    check_arity(builtin.name, builtin.arity, args.len())?;
+
+    match name
+    {
+        "assert" if !args[0].as_bool()? => Err(CalcError::AssertionFailed(String::new())),
+        "assert" => Ok(Value::Bool(true)),
+        "assert_eq" if !compare_values(&Token::Equal, &args[0], &args[1])? => {
+            Err(CalcError::AssertionFailed(format!("{} != {}", args[0], args[1])))
+        }
+        "assert_eq" => Ok(Value::Bool(true)),
+        "random" => Ok(Value::Float(rng.next_float())),
+        "randint" => {
+            let (low, high) = (args[0].as_int()?, args[1].as_int()?);
+            if low > high{
+                return Err(CalcError::DomainError(format!("randint needs a <= b, found {} > {}", low, high)));
+            }
+            Ok(Value::Int(rng.range(low, high)))
+        }
+        "divmod" => divmod(&args[0], &args[1]),
+        "ln" => logarithm(name, args[0].as_float()?, std::f64::consts::E),
+        "log" => logarithm(name, args[0].as_float()?, 10.0),
+        "log2" => logarithm(name, args[0].as_float()?, 2.0),
+        "exp" => {
+            let result = args[0].as_float()?.exp();
+            if result.is_infinite(){
+                return Err(CalcError::Overflow);
+            }
+            Ok(Value::Float(result))
+        }
+        "sqrt" => {
+            let x = args[0].as_float()?;
+            if x < 0.0{
+                return Err(CalcError::DomainError(format!("{} is undefined for {}", name, x)));
+            }
+            Ok(Value::Float(x.sqrt()))
+        }
+        "pow" => binary_op(&Token::Power, &args[0], &args[1]),
+        "signum" => signum(&args[0]),
+        "copysign" => copysign(&args[0], &args[1]),
+        "int" => to_int(&args[0]),
+        "bool" => to_bool(&args[0]),
+        "max" => extremum(args, Ordering::Greater),
+        "min" => extremum(args, Ordering::Less),
+        "sin" => Ok(Value::Float(to_radians(args[0].as_float()?, angle).sin())),
+        "cos" => Ok(Value::Float(to_radians(args[0].as_float()?, angle).cos())),
+        "tan" => Ok(Value::Float(to_radians(args[0].as_float()?, angle).tan())),
+        "asin" | "acos" => inverse_trig(name, args[0].as_float()?, angle),
+        "atan" => Ok(from_radians(args[0].as_float()?.atan(), angle)),
+        "atan2" => Ok(from_radians(args[0].as_float()?.atan2(args[1].as_float()?), angle)),
+        _ if builtin.lazy => unreachable!("special form {} is run by the interpreter", name),
+        _ => unreachable!("{} is listed in BUILTINS but not implemented", name),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{evaluate, CalcError, Value};
+
+    use super::checked_divmod;
+
+    fn value(input: &str) -> Value
+    {
+        evaluate(input).unwrap().value
+    }
+
+    fn assert_close(input: &str, expected: f64)
+    {
+        match value(input)
+        {
+            Value::Float(found) => assert!((found - expected).abs() < 1e-12, "{} is {}, expected {}", input, found, expected),
+            other => panic!("{} is {:?}, expected a float", input, other),
+        }
+    }
+
+    fn is_domain_error(input: &str) -> bool
+    {
+        matches!(evaluate(input), Err(CalcError::DomainError(_)))
+    }
+
+    #[test]
+    fn divmod_truncates_toward_zero()
+    {
+        assert_eq!(checked_divmod(7, 2), Ok((3, 1)));
+        assert_eq!(checked_divmod(-7, 2), Ok((-3, -1)));
+        assert_eq!(checked_divmod(7, -2), Ok((-3, 1)));
+        assert_eq!(checked_divmod(-7, -2), Ok((3, -1)));
+        assert_eq!(checked_divmod(6, 3), Ok((2, 0)));
+
+        assert_eq!(value("divmod(-7, 2)"), Value::Tuple(vec![Value::Int(-3), Value::Int(-1)]));
+        assert_eq!(value("divmod(7, 2)"), Value::Tuple(vec![Value::Int(3), Value::Int(1)]));
+    }
+
+    #[test]
+    fn divmod_by_zero_fails()
+    {
+        assert_eq!(checked_divmod(7, 0), Err(CalcError::DivisionByZero));
+        assert_eq!(evaluate("divmod(7, 0)"), Err(CalcError::DivisionByZero));
+    }
+
+    #[test]
+    fn logarithms_and_exp()
+    {
+        assert_close("ln(e)", 1.0);
+        assert_close("log(1000)", 3.0);
+        assert_close("log2(8)", 3.0);
+        assert_close("exp(0)", 1.0);
+        assert_close("exp(1)", std::f64::consts::E);
+        assert_close("ln(exp(2))", 2.0);
+    }
+
+    #[test]
+    fn logarithms_reject_non_positive_arguments()
+    {
+        assert!(is_domain_error("ln(0)"));
+        assert!(is_domain_error("log(-5)"));
+        assert!(is_domain_error("log2(0)"));
+    }
+
+    #[test]
+    fn trigonometry()
+    {
+        assert_close("sin(0)", 0.0);
+        assert_close("cos(0)", 1.0);
+        assert_close("atan2(1, 1)", std::f64::consts::FRAC_PI_4);
+        assert_close("asin(1)", std::f64::consts::FRAC_PI_2);
+        assert!(is_domain_error("asin(2)"));
+        assert!(is_domain_error("acos(-1.5)"));
+    }
+
+    #[test]
+    fn signum_and_copysign()
+    {
+        assert_eq!(value("signum(3)"), Value::Int(1));
+        assert_eq!(value("signum(-3)"), Value::Int(-1));
+        assert_eq!(value("signum(0)"), Value::Int(0));
+        assert_eq!(value("signum(-0.5)"), Value::Float(-1.0));
+        assert_eq!(value("signum(0.0)"), Value::Float(0.0));
+
+        assert_eq!(value("copysign(3, -1)"), Value::Int(-3));
+        assert_eq!(value("copysign(-3, 2)"), Value::Int(3));
+        assert_eq!(value("copysign(-2.5, 1)"), Value::Float(2.5));
+    }
+
+    #[test]
+    fn assertions_pass_or_fail()
+    {
+        assert_eq!(value("assert(2 + 2 == 4)"), Value::Bool(true));
+        assert_eq!(evaluate("assert(1 == 2)"), Err(CalcError::AssertionFailed(String::new())));
+        assert_eq!(evaluate("assert(1 == 2)").unwrap_err().to_string(), "assertion failed");
+
+        assert_eq!(value("assert_eq(1 + 1, 2.0)"), Value::Bool(true));
+        assert_eq!(evaluate("assert_eq(1, 2)").unwrap_err().to_string(), "assertion failed: 1 != 2");
+    }
+
+    #[test]
+    fn booleans_and_numbers_convert_explicitly()
+    {
+        assert_eq!(value("int(3 < 5) + 1"), Value::Int(2));
+        assert_eq!(value("int(true)"), Value::Int(1));
+        assert_eq!(value("int(-2.7)"), Value::Int(-2));
+        assert_eq!(value("bool(0)"), Value::Bool(false));
+        assert_eq!(value("bool(0.5)"), Value::Bool(true));
+    }
+}
@@ -0,0 +1,737 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Instant;
+
+use crate::environment::{Environment, UserFunction};
+use crate::error::CalcError;
+use crate::functions::{call_function, check_arity, checked_divmod, constant, find_builtin, Arity};
+use crate::ast::Ast;
+#[cfg(feature = "bignum")]
+use crate::bignum::BigInt;
+use crate::options::Options;
+use crate::token::Token;
+use crate::value::{StatementResult, Value};
+
+//Integer exponentiation, `None` on overflow.
+//
+//        Negative exponents are rejected since the result wouldn't be an
+//        integer (use a float base such as 2.0 ^ -1 instead), and 0 ^ 0 is
+//        defined as 1, as in most languages.
+fn int_power(base: i64, exponent: i64) -> Result<Option<i64>, CalcError>
+{
+    if exponent < 0{
+        return Err(CalcError::DomainError(format!("negative exponent {} in integer power", exponent)));
+    }
+
+    match u32::try_from(exponent)
+    {
+        Ok(exponent) => Ok(base.checked_pow(exponent)),
+        Err(_e) if base == 0 || base == 1 => Ok(Some(base)),
+        Err(_e) if base == -1 => Ok(Some(if exponent % 2 == 0 { 1 } else { -1 })),
+        Err(_e) => Ok(None),
+    }
+}
+
+//Float exponentiation.
+//
+//        Results are always real; there are no complex numbers. A negative
+//        base only has a real power for a whole exponent, so (-8) ^ 0.5
+//        and even (-8) ^ (1.0 / 3) are domain errors rather than NaN or a
+//        complex root: 1.0 / 3 isn't exactly a third, so there is no odd
+//        root to take. Use a positive base (-(8 ^ (1.0 / 3)) is -2) for
+//        cube roots. (-8) ^ (1 / 3) is not a root at all: 1 / 3 is integer
+//        division, so the exponent is 0 and the result is 1.
+fn float_power(base: f64, exponent: f64) -> Result<f64, CalcError>
+{
+    if base < 0.0 && exponent.is_finite() && exponent.fract() != 0.0{
+        return Err(CalcError::DomainError(format!("({}) ^ {} has no real value", base, exponent)));
+    }
+    Ok(base.powf(exponent))
+}
+
+//Redo an integer operation that overflowed an i64.
+#[cfg(not(feature = "bignum"))]
+fn overflowed(_operator: &Token, _left: &Value, _right: &Value) -> Result<Value, CalcError>
+{
+    Err(CalcError::Overflow)
+}
+
+//Redo an integer operation that overflowed an i64 with exact big integers.
+#[cfg(feature = "bignum")]
+fn overflowed(operator: &Token, left: &Value, right: &Value) -> Result<Value, CalcError>
+{
+    big_binary_op(operator, as_bigint(left).unwrap(), as_bigint(right).unwrap())
+}
+
+#[cfg(feature = "bignum")]
+pub fn as_bigint(value: &Value) -> Option<BigInt>
+{
+    match value
+    {
+        Value::Int(integer) => Some(BigInt::from_i64(*integer)),
+        Value::BigInt(big) => Some(big.clone()),
+        _ => None,
+    }
+}
+
+//Integer arithmetic where at least one side doesn't fit in an i64.
+#[cfg(feature = "bignum")]
+fn big_binary_op(operator: &Token, left: BigInt, right: BigInt) -> Result<Value, CalcError>
+{
+    let result = match operator
+    {
+        Token::Plus => left.add(&right),
+        Token::Minus => left.sub(&right),
+        Token::Mul => left.mul(&right),
+        Token::Div => left.div(&right)?,
+        Token::Power => {
+            let exponent = right.to_i64().ok_or(CalcError::Overflow)?;
+            if exponent < 0{
+                return Err(CalcError::DomainError(format!("negative exponent {} in integer power", exponent)));
+            }
+            left.pow(exponent as u64)?
+        }
+        _ => unreachable!("not a binary operator: {:?}", operator)
+    };
+    Ok(Value::from_bigint(result))
+}
+
+//The operands of a binary operator brought to a common type.
+enum Operands
+{
+    Ints(i64, i64),
+    #[cfg(feature = "bignum")]
+    BigInts(BigInt, BigInt),
+    Floats(f64, f64)
+}
+
+//Promote two numbers to a common type, the one place arithmetic and
+//comparisons decide how mixed operands combine.
+//
+//        Two integers stay integers (an `Int` with a `BigInt` is a pair of
+//        `BigInt`s), and anything mixed with a float becomes a float: 2 + 3.0
+//        is 5.0, 4 / 2.0 is 2.0 and 2 == 2.0 is true. Booleans and tuples
+//        aren't numbers and are type errors.
+fn promote(left: &Value, right: &Value) -> Result<Operands, CalcError>
+{
+    if let (Value::Int(l), Value::Int(r)) = (left, right){
+        return Ok(Operands::Ints(*l, *r));
+    }
+    #[cfg(feature = "bignum")]
+    if let (Some(l), Some(r)) = (as_bigint(left), as_bigint(right)){
+        return Ok(Operands::BigInts(l, r));
+    }
+    Ok(Operands::Floats(left.as_float()?, right.as_float()?))
+}
+
+//Evaluate a comparison operator.
+//
+//        Integers compare exactly; other numbers are compared as floats, so
+//        every comparison with NaN is false except `!=`. Booleans and tuples
+//        can only be tested for (in)equality, tuples element by element,
+//        and never equal a number.
+pub fn compare_values(operator: &Token, left: &Value, right: &Value) -> Result<bool, CalcError>
+{
+    let ordering = match (left, right)
+    {
+        (Value::Tuple(l), Value::Tuple(r)) if *operator == Token::Equal || *operator == Token::NotEqual => {
+            let mut equal = l.len() == r.len();
+            for (l, r) in l.iter().zip(r){
+                equal = equal && compare_values(&Token::Equal, l, r)?;
+            }
+            return Ok(equal == (*operator == Token::Equal));
+        }
+        (Value::Bool(l), Value::Bool(r)) if *operator == Token::Equal || *operator == Token::NotEqual => {
+            return Ok((l == r) == (*operator == Token::Equal));
+        }
+        _ => match promote(left, right)?
+        {
+            Operands::Ints(l, r) => Some(l.cmp(&r)),
+            #[cfg(feature = "bignum")]
+            Operands::BigInts(l, r) => Some(l.compare(&r)),
+            Operands::Floats(l, r) => l.partial_cmp(&r),
+        }
+    };
+
+    let ordering = match ordering
+    {
+        Some(ordering) => ordering,
+        None => return Ok(*operator == Token::NotEqual),
+    };
+    Ok(match operator
+    {
+        Token::Equal => ordering == Ordering::Equal,
+        Token::NotEqual => ordering != Ordering::Equal,
+        Token::Less => ordering == Ordering::Less,
+        Token::LessEqual => ordering != Ordering::Greater,
+        Token::Greater => ordering == Ordering::Greater,
+        Token::GreaterEqual => ordering != Ordering::Less,
+        _ => unreachable!("not a comparison operator: {:?}", operator)
+    })
+}
+
+//Apply `&&` or `||` to two booleans. `right` is only called when `left`
+//doesn't already decide the result, so `false && 1 / 0` is false.
+pub fn logical_op<F>(operator: &Token, left: &Value, right: F) -> Result<Value, CalcError>
+    where F: FnOnce() -> Result<Value, CalcError>
+{
+    let left = left.as_bool()?;
+    if left == (*operator == Token::Or){
+        return Ok(Value::Bool(left));
+    }
+    right()?.as_bool().map(Value::Bool)
+}
+
+//Apply a binary operator.
+//
+//        Two integers use checked integer arithmetic (division truncates);
+//        if either side is a float both are promoted to floats. Comparisons
+//        give true or false; booleans don't take part in arithmetic.
+pub fn binary_op(operator: &Token, left: &Value, right: &Value) -> Result<Value, CalcError>
+{
+    if operator.is_comparison(){
+        return compare_values(operator, left, right).map(Value::Bool);
+    }
+    if operator.is_logical(){
+        return logical_op(operator, left, || Ok(right.clone()));
+    }
+    //`of` is just a spelling of multiplication.
+    let operator = if *operator == Token::Of { &Token::Mul } else { operator };
+
+    let (left, right) = match promote(left, right)?
+    {
+        Operands::Ints(l, r) => {
+            let result = match operator
+            {
+                Token::Plus => l.checked_add(r),
+                Token::Minus => l.checked_sub(r),
+                Token::Mul => l.checked_mul(r),
+                //Only i64::MIN / -1 overflows.
+                Token::Div => match checked_divmod(l, r)
+                {
+                    Ok((quotient, _)) => Some(quotient),
+                    Err(CalcError::Overflow) => None,
+                    Err(e) => return Err(e),
+                },
+                Token::Power => int_power(l, r)?,
+                _ => unreachable!("not a binary operator: {:?}", operator)
+            };
+            return match result
+            {
+                Some(result) => Ok(Value::Int(result)),
+                None => overflowed(operator, left, right),
+            };
+        }
+        #[cfg(feature = "bignum")]
+        Operands::BigInts(l, r) => return big_binary_op(operator, l, r),
+        Operands::Floats(l, r) => (l, r),
+    };
+    let result = match operator
+    {
+        Token::Plus => left + right,
+        Token::Minus => left - right,
+        Token::Mul => left * right,
+        Token::Div if right == 0.0 => return Err(CalcError::DivisionByZero),
+        Token::Div => left / right,
+        Token::Power => float_power(left, right)?,
+        _ => unreachable!("not a binary operator: {:?}", operator)
+    };
+    Ok(Value::Float(result))
+}
+
+//A note for an integer division that dropped a remainder, e.g. 7 / 2.
+pub fn truncation_note(left: &Value, right: &Value) -> Option<String>
+{
+    match (left, right)
+    {
+        (Value::Int(left), Value::Int(right)) if left.checked_rem(*right).unwrap_or(0) != 0 => Some(format!(
+            "{} / {} truncated to {} (use a float operand such as {}.0 / {} for the exact result)",
+            left, right, left / right, left, right
+        )),
+        _ => None,
+    }
+}
+
+//Whether `value` is a float or can be converted to one exactly.
+fn converts_exactly(value: &Value) -> bool
+{
+    match value
+    {
+        Value::Int(integer) => (*integer as f64) as i128 == *integer as i128,
+        #[cfg(feature = "bignum")]
+        Value::BigInt(_) => false,
+        _ => true,
+    }
+}
+
+//Whether computing `result` from `operands` silently lost information:
+//an integer too large to convert to a float exactly was mixed with
+//floats, or finite numbers overflowed to infinity.
+fn lost_precision(operands: &[Value], result: &Value) -> bool
+{
+    let floats = matches!(result, Value::Float(_)) || operands.iter().any(|operand| matches!(operand, Value::Float(_)));
+    let promoted = floats && !operands.iter().all(converts_exactly);
+    let overflowed = matches!(result, Value::Float(float) if float.is_infinite())
+        && operands.iter().all(|operand| operand.as_float().is_ok_and(f64::is_finite));
+    promoted || overflowed
+}
+
+//The variable a special form binds, which must be written as a bare name.
+fn bound_name<'n>(function: &str, arg: &'n Ast) -> Result<&'n str, CalcError>
+{
+    match arg
+    {
+        Ast::Variable(name) => Ok(name),
+        _ => Err(CalcError::TypeError(format!("{} expects a variable name to bind", function))),
+    }
+}
+
+//How deeply user-defined functions may call each other before the
+//evaluation is abandoned, so runaway recursion fails cleanly.
+const MAX_CALL_DEPTH: usize = 100;
+
+//Most terms a single `sum` or `product` may evaluate.
+const MAX_TERMS: i64 = 1_000_000;
+
+//Tree-walking evaluator for the AST built by the parser.
+pub struct Interpreter<'a>
+{
+    env: &'a mut Environment,
+    options: &'a Options,
+    //Parameter bindings of the user-defined functions currently running,
+    //innermost last.
+    scopes: Vec<HashMap<String, Value>>,
+    //Notes about the evaluation that aren't errors, in the order raised.
+    notes: Vec<String>,
+    //Set once any step loses information, see `evaluate_checked`.
+    lossy: bool,
+    //When the options' time limit runs out.
+    deadline: Option<Instant>
+}
+
+impl<'a> Interpreter<'a>
+{
+    //Look up a variable: the innermost function parameters first, then
+    //the global variables, the memory register (`mem`), the previous
+    //result (`@`) and the built-in constants, and finally the options'
+    //value for undefined variables.
+    fn variable(&self, name: &str) -> Result<Value, CalcError>
+    {
+        if let Some(value) = self.scopes.last().and_then(|scope| scope.get(name)){
+            return Ok(value.clone());
+        }
+
+        match self.env.variables.get(name)
+        {
+            Some(value) => Ok(value.clone()),
+            None if name == "mem" => Ok(self.env.memory.clone()),
+            None if name == "@" => self.env.previous.clone().ok_or(CalcError::NoPreviousResult),
+            None => constant(name)
+                .or_else(|| self.options.undefined_value.clone())
+                .ok_or_else(|| CalcError::UnknownVariable(String::from(name))),
+        }
+    }
+
+    pub fn unary_op(operator: &Token, operand: Value) -> Result<Value, CalcError>
+    {
+        match (operator, operand)
+        {
+            (Token::Not, operand) => Ok(Value::Bool(!operand.as_bool()?)),
+            (Token::Percent, operand) => Ok(Value::Float(operand.as_float()? / 100.0)),
+            (Token::Plus, operand) => {operand.as_float()?; Ok(operand)},
+            (Token::Minus, Value::Float(float)) => Ok(Value::Float(-float)),
+            #[cfg(feature = "bignum")]
+            (Token::Minus, Value::BigInt(big)) => Ok(Value::from_bigint(big.neg())),
+            //Only i64::MIN overflows, as 0 - i64::MIN.
+            (Token::Minus, operand) => match operand.as_int()?.checked_neg()
+            {
+                Some(negated) => Ok(Value::Int(negated)),
+                None => overflowed(&Token::Minus, &Value::Int(0), &operand),
+            },
+            (operator, _) => unreachable!("not a unary operator: {:?}", operator)
+        }
+    }
+
+    //Call a user-defined function, binding its parameters in a new scope.
+    fn call_user_function(&mut self, name: &str, function: UserFunction, args: Vec<Value>) -> Result<Value, CalcError>
+    {
+        if args.len() != function.params.len(){
+            return Err(CalcError::WrongArgumentCount { name: String::from(name), expected: Arity::Exactly(function.params.len()), found: args.len() });
+        }
+        if self.scopes.len() >= MAX_CALL_DEPTH{
+            return Err(CalcError::RecursionLimit);
+        }
+
+        self.scopes.push(function.params.into_iter().zip(args).collect());
+        let result = self.visit(&function.body);
+        self.scopes.pop();
+        result
+    }
+
+    //Evaluate `body` with `name` bound to `value`, on top of whatever the
+    //innermost scope already binds. The binding is gone afterwards.
+    fn visit_with(&mut self, name: &str, value: Value, body: &Ast) -> Result<Value, CalcError>
+    {
+        if self.scopes.len() >= MAX_CALL_DEPTH{
+            return Err(CalcError::RecursionLimit);
+        }
+
+        let mut scope = self.scopes.last().cloned().unwrap_or_default();
+        scope.insert(String::from(name), value);
+        self.scopes.push(scope);
+        let result = self.visit(body);
+        self.scopes.pop();
+        result
+    }
+
+    //Run a special form, whose arguments are expressions to evaluate
+    //repeatedly rather than values.
+    //
+    //        sum(i, from, to, expr) and product(i, from, to, expr) evaluate
+    //        expr for each integer i in from..=to (an empty range gives 0
+    //        and 1 respectively). deriv(expr, x, point) is the central
+    //        difference (expr(point + h) - expr(point - h)) / 2h, with h
+    //        scaled to the size of point.
+    fn special_form(&mut self, name: &str, args: &[Ast]) -> Result<Value, CalcError>
+    {
+        let builtin = find_builtin(name).unwrap();
+        check_arity(builtin.name, builtin.arity, args.len())?;
+
+        match name
+        {
+            "sum" | "product" => {
+                let variable = bound_name(name, &args[0])?;
+                let (from, to) = (self.visit(&args[1])?.as_int()?, self.visit(&args[2])?.as_int()?);
+                if to.saturating_sub(from) >= MAX_TERMS{
+                    return Err(CalcError::DomainError(format!("{} over more than {} terms", name, MAX_TERMS)));
+                }
+
+                let (operator, mut total) = if name == "sum" { (Token::Plus, Value::Int(0)) } else { (Token::Mul, Value::Int(1)) };
+                for i in from..=to{
+                    let term = self.visit_with(variable, Value::Int(i), &args[3])?;
+                    total = binary_op(&operator, &total, &term)?;
+                }
+                Ok(total)
+            }
+            "deriv" => {
+                let variable = bound_name(name, &args[1])?;
+                let point = self.visit(&args[2])?.as_float()?;
+                let h = 1e-5 * point.abs().max(1.0);
+                let above = self.visit_with(variable, Value::Float(point + h), &args[0])?.as_float()?;
+                let below = self.visit_with(variable, Value::Float(point - h), &args[0])?.as_float()?;
+                Ok(Value::Float((above - below) / (2.0 * h)))
+            }
+            _ => unreachable!("not a special form: {}", name),
+        }
+    }
+
+    //Apply a binary operator to evaluated operands, noting truncated
+    //integer divisions and lost precision.
+    fn arithmetic(&mut self, operator: &Token, left: Value, right: Value) -> Result<Value, CalcError>
+    {
+        if *operator == Token::Div{
+            if let Some(note) = truncation_note(&left, &right){
+                if let (true, Value::Int(l), Value::Int(r)) = (self.options.strict, &left, &right){
+                    return Err(CalcError::InexactDivision(*l, *r));
+                }
+                self.notes.push(note);
+                self.lossy = true;
+            }
+        }
+        let result = binary_op(operator, &left, &right)?;
+        self.lossy |= lost_precision(&[left, right], &result);
+        Ok(result)
+    }
+
+    //Call the function `name`: user-defined functions shadow native ones,
+    //which shadow the built-ins.
+    fn call(&mut self, name: &str, args: &[Ast]) -> Result<Value, CalcError>
+    {
+        let function = self.env.functions.get(name).cloned();
+        let native = self.env.natives.contains_key(name);
+        if function.is_none() && !native && find_builtin(name).is_some_and(|builtin| builtin.lazy){
+            return self.special_form(name, args);
+        }
+
+        let args = args.iter().map(|arg| self.visit(arg)).collect::<Result<Vec<Value>, CalcError>>()?;
+        match function
+        {
+            Some(function) => self.call_user_function(name, function, args),
+            None if native => {
+                let (arity, function) = &self.env.natives[name];
+                check_arity(name, *arity, args.len())?;
+                let args = args.iter().map(Value::as_float).collect::<Result<Vec<f64>, CalcError>>()?;
+                function(&args).map(Value::Float)
+            }
+            None => {
+                let result = call_function(name, args.clone(), self.options.angle, &mut self.env.rng)?;
+                self.lossy |= lost_precision(&args, &result);
+                Ok(result)
+            }
+        }
+    }
+
+    //Evaluate `node`. It recurses once per level of the tree, so the work
+    //for operators and calls is left to helpers to keep its stack frame
+    //small.
+    pub fn visit(&mut self, node: &Ast) -> Result<Value, CalcError>
+    {
+        if let (Some(deadline), Some(limit)) = (self.deadline, self.options.time_limit){
+            if Instant::now() >= deadline{
+                return Err(CalcError::Timeout(limit));
+            }
+        }
+
+        match node
+        {
+            Ast::Integer(integer) => Ok(Value::Int(*integer)),
+            Ast::Float(float) => Ok(Value::Float(*float)),
+            Ast::Bool(boolean) => Ok(Value::Bool(*boolean)),
+            Ast::Variable(name) => self.variable(name),
+            Ast::UnaryOp(operator, operand) => {
+                let operand = self.visit(operand)?;
+                Interpreter::unary_op(operator, operand)
+            }
+            //Only the branch taken is evaluated.
+            Ast::If(condition, then, otherwise) => {
+                if self.visit(condition)?.as_bool()? { self.visit(then) } else { self.visit(otherwise) }
+            }
+            Ast::BinOp(left, operator, right) if operator.is_logical() => {
+                let left = self.visit(left)?;
+                logical_op(operator, &left, || self.visit(right))
+            }
+            Ast::BinOp(left, operator, right) => {
+                let left = self.visit(left)?;
+                let right = self.visit(right)?;
+                self.arithmetic(operator, left, right)
+            }
+            Ast::Call(name, args) => self.call(name, args),
+            Ast::Assign(name, value) => {
+                let value = self.visit(value)?;
+                self.env.variables.insert(name.clone(), value.clone());
+                Ok(value)
+            }
+            Ast::FunctionDef(name, _, _) => Err(CalcError::TypeError(format!("{} is a function definition, not a value", name))),
+        }
+    }
+
+    //Evaluate a parsed statement, noting whether it was an assignment
+    //or a function definition.
+    pub fn run(&mut self, node: &Ast) -> Result<StatementResult, CalcError>
+    {
+        if let Ast::FunctionDef(name, params, body) = node{
+            self.env.functions.insert(name.clone(), UserFunction{
+                params: params.clone(),
+                body: (**body).clone(),
+            });
+            return Ok(StatementResult::Definition(name.clone()));
+        }
+
+        let value = self.visit(node)?;
+        match node
+        {
+            Ast::Assign(name, _) => Ok(StatementResult::Assignment(name.clone(), value)),
+            _ => Ok(StatementResult::Expression(value)),
+        }
+    }
+
+    //Hand over the notes raised so far.
+    pub fn take_notes(&mut self) -> Vec<String>
+    {
+        std::mem::take(&mut self.notes)
+    }
+
+    //Whether any step so far lost information.
+    pub fn lossy(&self) -> bool
+    {
+        self.lossy
+    }
+
+    pub fn create_interpreter(env: &'a mut Environment, options: &'a Options) -> Interpreter<'a>
+    {
+        Interpreter{
+            env,
+            options,
+            scopes: Vec::new(),
+            notes: Vec::new(),
+            lossy: false,
+            deadline: options.time_limit.and_then(|limit| Instant::now().checked_add(limit)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{evaluate, CalcError, Calculator, Value};
+
+    use super::truncation_note;
+
+    fn value(input: &str) -> Result<Value, CalcError>
+    {
+        evaluate(input).map(|outcome| outcome.value)
+    }
+
+    #[test]
+    fn zero_to_the_zero_is_one()
+    {
+        assert_eq!(value("0 ^ 0"), Ok(Value::Int(1)));
+        assert_eq!(value("0.0 ^ 0"), Ok(Value::Float(1.0)));
+    }
+
+    //Results just past an i64 in every direction integers can overflow.
+    const OVERFLOWING: &[&str] = &[
+        "9223372036854775807 + 1",
+        "-9223372036854775807 - 2",
+        "3037000500 * 3037000500",
+        "2 ^ 63",
+        "(-9223372036854775807 - 1) / -1",
+        "-(-9223372036854775807 - 1)",
+    ];
+
+    #[cfg(not(feature = "bignum"))]
+    #[test]
+    fn integer_overflow_is_an_error()
+    {
+        for input in OVERFLOWING{
+            assert_eq!(value(input), Err(CalcError::Overflow), "{}", input);
+        }
+        assert_eq!(value("divmod(-9223372036854775807 - 1, -1)"), Err(CalcError::Overflow));
+        assert_eq!(value("9223372036854775807 + 0"), Ok(Value::Int(i64::MAX)));
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn integer_overflow_is_exact_with_bignum()
+    {
+        let results = ["9223372036854775808", "-9223372036854775809", "9223372037000250000", "9223372036854775808",
+            "9223372036854775808", "9223372036854775808"];
+        for (input, result) in OVERFLOWING.iter().zip(results){
+            assert_eq!(value(input).map(|value| value.to_string()), Ok(String::from(result)), "{}", input);
+        }
+
+        assert_eq!(value("2 ^ 100").unwrap().to_string(), "1267650600228229401496703205376");
+        assert_eq!(value("divmod(2 ^ 64, 3)").unwrap().to_string(), "(6148914691236517205, 1)");
+        assert_eq!(value("divmod(-(2 ^ 64), 3)").unwrap().to_string(), "(-6148914691236517205, -1)");
+        assert_eq!(value("divmod(-9223372036854775807 - 1, -1)").unwrap().to_string(), "(9223372036854775808, 0)");
+        assert_eq!(value("divmod(2 ^ 64, 0)"), Err(CalcError::DivisionByZero));
+        //Results that fit are plain integers again.
+        assert_eq!(value("2 ^ 64 - 2 ^ 64 + 1"), Ok(Value::Int(1)));
+    }
+
+    #[test]
+    fn only_divisions_with_a_remainder_are_noted()
+    {
+        assert_eq!(truncation_note(&Value::Int(7), &Value::Int(2)).as_deref(),
+            Some("7 / 2 truncated to 3 (use a float operand such as 7.0 / 2 for the exact result)"));
+        assert_eq!(truncation_note(&Value::Int(8), &Value::Int(2)), None);
+        assert_eq!(truncation_note(&Value::Float(7.0), &Value::Int(2)), None);
+        assert_eq!(truncation_note(&Value::Int(7), &Value::Int(0)), None);
+
+        let mut calculator = Calculator::new();
+        calculator.eval("7 / 2").unwrap();
+        assert_eq!(calculator.notes().len(), 1);
+        calculator.eval("8 / 2").unwrap();
+        assert!(calculator.notes().is_empty());
+    }
+
+    #[test]
+    fn derivatives_are_estimated_numerically()
+    {
+        for (input, expected) in [("deriv(x * x, x, 3)", 6.0), ("deriv(sin(x), x, 0)", 1.0), ("deriv(3 * t + 1, t, -2)", 3.0)]{
+            match value(input)
+            {
+                Ok(Value::Float(slope)) => assert!((slope - expected).abs() < 1e-6, "{} is {}", input, slope),
+                other => panic!("{} is {:?}", input, other),
+            }
+        }
+        assert!(matches!(value("deriv(x, 2, 3)"), Err(CalcError::TypeError(_))));
+    }
+
+    #[test]
+    fn sums_bind_their_variable_only_inside()
+    {
+        let mut calculator = Calculator::new();
+        assert_eq!(calculator.eval("sum(i, 1, 5, i)").unwrap().into_value(), Some(Value::Int(15)));
+        assert_eq!(calculator.eval("i"), Err(CalcError::UnknownVariable(String::from("i"))));
+
+        calculator.eval("i = 10").unwrap();
+        assert_eq!(calculator.eval("sum(i, 1, 5, i * i)").unwrap().into_value(), Some(Value::Int(55)));
+        assert_eq!(calculator.variable("i"), Some(&Value::Int(10)));
+        assert_eq!(value("product(k, 1, 5, k)"), Ok(Value::Int(120)));
+    }
+
+    #[test]
+    fn strict_mode_rejects_only_inexact_divisions()
+    {
+        let mut calculator = Calculator::builder().strict(true).build();
+        assert_eq!(calculator.eval("6 / 3").unwrap().into_value(), Some(Value::Int(2)));
+        assert_eq!(calculator.eval("7 / 2"), Err(CalcError::InexactDivision(7, 2)));
+        assert_eq!(calculator.eval("7.0 / 2").unwrap().into_value(), Some(Value::Float(3.5)));
+    }
+
+    #[test]
+    fn fractional_exponents_stay_real()
+    {
+        assert_eq!(value("2 ^ 0.5"), Ok(Value::Float(std::f64::consts::SQRT_2)));
+        assert_eq!(value("4 ^ 0.5"), Ok(Value::Float(2.0)));
+        assert_eq!(value("8 ^ (1.0 / 3)"), Ok(Value::Float(2.0)));
+
+        //A negative base has no real fractional power.
+        assert_eq!(value("(-8) ^ (1.0 / 3)"), Err(CalcError::DomainError(String::from("(-8) ^ 0.3333333333333333 has no real value"))));
+        assert!(matches!(value("(-8) ^ 0.5"), Err(CalcError::DomainError(_))));
+        assert_eq!(value("(-8.0) ^ 3"), Ok(Value::Float(-512.0)));
+        //An integer exponent of 0, since 1 / 3 truncates.
+        assert_eq!(value("(-8) ^ (1 / 3)"), Ok(Value::Int(1)));
+    }
+
+    #[test]
+    fn comparisons_give_booleans_that_are_not_numbers()
+    {
+        let mut calculator = Calculator::new();
+        let result = calculator.eval("3 < 5").unwrap();
+        assert_eq!(calculator.format(&result), "true");
+
+        let not_a_number = || Err(CalcError::TypeError(String::from("expected a number, found a boolean")));
+        assert_eq!(value("true + 1"), not_a_number());
+        assert_eq!(value("1 + (2 > 3)"), not_a_number());
+        assert_eq!(value("-true"), not_a_number());
+    }
+
+    #[test]
+    fn percentages_of_a_value()
+    {
+        assert_eq!(value("20% of 150"), Ok(Value::Float(30.0)));
+        assert_eq!(value("10% of 200 + 5"), Ok(Value::Float(25.0)));
+        assert_eq!(value("10% of 200 + 5 == 25"), Ok(Value::Bool(true)));
+        assert_eq!(value("50%"), Ok(Value::Float(0.5)));
+    }
+
+    #[test]
+    fn mixed_operands_are_promoted_to_floats()
+    {
+        let cases = [
+            ("1 + 2.5", 3.5), ("2.5 + 1", 3.5), ("3 - 0.5", 2.5), ("2 * 1.5", 3.0), ("3 / 2.0", 1.5),
+            ("3.0 / 2", 1.5), ("2.0 ^ 2", 4.0), ("50% of 3", 1.5), ("3 of 2.0", 6.0),
+        ];
+        for (input, expected) in cases{
+            assert_eq!(value(input), Ok(Value::Float(expected)), "{}", input);
+        }
+        assert_eq!(value("2 == 2.0"), Ok(Value::Bool(true)));
+        assert_eq!(value("2 != 2.0"), Ok(Value::Bool(false)));
+        assert_eq!(value("1 < 1.5"), Ok(Value::Bool(true)));
+        assert_eq!(value("2 + 3"), Ok(Value::Int(5)));
+    }
+
+    #[test]
+    fn undefined_variables_in_lenient_and_strict_mode()
+    {
+        assert_eq!(Calculator::new().eval("x + 1"), Err(CalcError::UnknownVariable(String::from("x"))));
+
+        let mut lenient = Calculator::builder().treat_undefined_as_zero(true).build();
+        assert_eq!(lenient.eval("x + 1").unwrap().into_value(), Some(Value::Int(1)));
+        let mut custom = Calculator::builder().undefined_value(Value::Float(0.5)).build();
+        assert_eq!(custom.eval("x + 1").unwrap().into_value(), Some(Value::Float(1.5)));
+    }
+}
@@ -0,0 +1,559 @@
+use std::io::{self, Write};
+
+use crate::error::CalcError;
+use crate::options::Options;
+use crate::token::Token;
+
+fn is_identifier_char(c: char) -> bool
+{
+    c.is_alphanumeric() || c == '_'
+}
+
+pub struct Lexer
+{
+    //The input as characters, so `pos` indexes characters rather than
+    //bytes and non-ASCII input (like a non-breaking space) is safe.
+    text: Vec<char>,
+    pos: usize,
+    current_char: Option<char>,
+    //Position where the most recently returned token starts.
+    token_start: usize,
+    //Read `1:30:00` as a number of seconds (see `evaluate_time`).
+    time_literals: bool,
+    //Every token returned so far, except `Eof`, if asked to keep them.
+    recorded: Option<Vec<Token>>,
+    //Accept digits grouped by this character, as in `1'000'000`.
+    group_separator: Option<char>,
+    //Where to log each token produced, for debugging.
+    trace: Option<Box<dyn Write>>,
+    //Other spellings of operators, such as `x` for `*`.
+    aliases: Vec<(String, Token)>,
+    //Whether the last token returned can end an operand, so that a word
+    //alias after it is read as the operator.
+    after_operand: bool
+}
+
+impl Lexer
+{
+    //Advance the `pos` pointer and set the `current_char` variable.
+    fn advance(&mut self)
+    {
+        self.pos += 1;
+        self.current_char = self.text.get(self.pos).copied();
+    }
+
+    fn skip_whitespaces(&mut self)
+    {
+        while self.current_char.is_some() && self.current_char.unwrap().is_whitespace(){
+            self.advance();
+        }
+    }
+
+    //Return the (multidigit) run of digits at the current position.
+    fn digits(&mut self) -> String {
+        let mut result = String::new();
+        while self.current_char.is_some() && self.current_char.unwrap().is_ascii_digit(){
+            result.push(self.current_char.unwrap());
+            self.advance();
+        }
+        result
+    }
+
+    //Whether the current character separates two groups of digits.
+    fn at_group_separator(&self) -> bool
+    {
+        self.current_char.is_some() && self.current_char == self.group_separator && self.peek().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    //Continue the leading digits of a number through groups of three
+    //separated by `group_separator`, returning the digits without the
+    //separators. The first group has one to three digits.
+    fn grouped_digits(&mut self, first: String) -> Result<String, CalcError>
+    {
+        let mut literal = first.clone();
+        let mut digits = first;
+        let mut valid = digits.len() <= 3;
+        while self.at_group_separator(){
+            literal.push(self.current_char.unwrap());
+            self.advance();
+            let group = self.digits();
+            literal.push_str(&group);
+            valid = valid && group.len() == 3;
+            digits.push_str(&group);
+        }
+
+        if !valid{
+            return Err(CalcError::MalformedNumber(literal));
+        }
+        Ok(digits)
+    }
+
+    //Return a number consumed from the input: an integer such as `42`,
+    //or a float when the digits are followed by a fractional part (`4.2`).
+    //
+    //        A literal never includes a sign; `+` and `-` are always lexed
+    //        as operators and applied by the parser. That is also why
+    //        -9223372036854775808 overflows: its digits alone don't fit.
+    fn number(&mut self) -> Result<Token, CalcError> {
+        let mut result = self.digits();
+
+        if result == "0" && (self.current_char == Some('x') || self.current_char == Some('X')){
+            return self.hex_number();
+        }
+
+        if self.at_group_separator(){
+            result = self.grouped_digits(result)?;
+        }
+
+        if self.time_literals && self.current_char == Some(':'){
+            return self.time_literal(result);
+        }
+
+        if self.current_char != Some('.'){
+            self.reject_identifier_suffix()?;
+            //Only digits were consumed, so the parse can fail only if the literal is too large.
+            return result.parse().map(Token::Integer).map_err(|_e| CalcError::Overflow);
+        }
+
+        result.push('.');
+        self.advance();
+        result.push_str(&self.digits());
+        self.reject_identifier_suffix()?;
+        Ok(Token::Float(result.parse().unwrap()))
+    }
+
+    //Return the run of hex digits at the current position, appending them to `literal`.
+    fn hex_digits(&mut self, literal: &mut String) -> Vec<u32> {
+        let mut result = Vec::new();
+        while let Some(digit) = self.current_char.and_then(|c| c.to_digit(16)){
+            literal.push(self.current_char.unwrap());
+            result.push(digit);
+            self.advance();
+        }
+        result
+    }
+
+    //Return a hexadecimal number whose leading `0` has been consumed:
+    //an integer such as `0xff`, or a float with a fraction and/or a
+    //power-of-two exponent, as in C: `0x1.8p1` is 1.5 * 2^1 = 3.0.
+    fn hex_number(&mut self) -> Result<Token, CalcError>
+    {
+        let mut literal = String::from("0");
+        literal.push(self.current_char.unwrap());
+        self.advance();
+
+        let whole = self.hex_digits(&mut literal);
+        let mut fraction = Vec::new();
+        let mut is_float = false;
+        if self.current_char == Some('.'){
+            literal.push('.');
+            self.advance();
+            fraction = self.hex_digits(&mut literal);
+            is_float = true;
+        }
+
+        let mut exponent = String::new();
+        if self.current_char == Some('p') || self.current_char == Some('P'){
+            literal.push(self.current_char.unwrap());
+            self.advance();
+            if let Some(sign) = self.current_char.filter(|c| *c == '+' || *c == '-'){
+                literal.push(sign);
+                exponent.push(sign);
+                self.advance();
+            }
+            let digits = self.digits();
+            if digits.is_empty(){
+                return Err(CalcError::MalformedNumber(literal));
+            }
+            literal.push_str(&digits);
+            exponent.push_str(&digits);
+            is_float = true;
+        }
+
+        if let Some(c) = self.current_char.filter(|c| is_identifier_char(*c) || *c == '.'){
+            literal.push(c);
+            return Err(CalcError::MalformedNumber(literal));
+        }
+        if whole.is_empty() && fraction.is_empty(){
+            return Err(CalcError::MalformedNumber(literal));
+        }
+
+        if !is_float{
+            let digits: String = literal.chars().skip(2).collect();
+            return i64::from_str_radix(&digits, 16).map(Token::Integer).map_err(|_e| CalcError::Overflow);
+        }
+
+        let mut value = whole.iter().fold(0.0, |value, digit| value * 16.0 + *digit as f64);
+        let mut scale = 1.0 / 16.0;
+        for digit in fraction{
+            value += digit as f64 * scale;
+            scale /= 16.0;
+        }
+        //An exponent too large for an i32 overflows (or underflows) anyway.
+        let exponent = match exponent.parse::<i32>()
+        {
+            Ok(exponent) => exponent,
+            Err(_e) if exponent.is_empty() => 0,
+            Err(_e) if exponent.starts_with('-') => i32::MIN,
+            Err(_e) => i32::MAX,
+        };
+        let value = value * 2f64.powi(exponent);
+        if value.is_infinite(){
+            return Err(CalcError::Overflow);
+        }
+        Ok(Token::Float(value))
+    }
+
+    //Return an `H:MM:SS` time literal, whose hours have already been
+    //consumed, as its total number of seconds.
+    fn time_literal(&mut self, hours: String) -> Result<Token, CalcError>
+    {
+        let mut parts = vec![hours];
+        while self.current_char == Some(':'){
+            self.advance();
+            parts.push(self.digits());
+        }
+        self.reject_identifier_suffix()?;
+
+        let literal = parts.join(":");
+        let valid = parts.len() == 3 && parts[1..].iter().all(|part| part.len() == 2 && part.as_str() < "60");
+        if !valid{
+            return Err(CalcError::InvalidTimeLiteral(literal));
+        }
+
+        let hours: i64 = parts[0].parse().map_err(|_e| CalcError::Overflow)?;
+        let minutes: i64 = parts[1].parse().unwrap();
+        let seconds: i64 = parts[2].parse().unwrap();
+        hours.checked_mul(3600)
+            .and_then(|total| total.checked_add(minutes * 60 + seconds))
+            .map(Token::Integer)
+            .ok_or(CalcError::Overflow)
+    }
+
+    //Fail if a number runs straight into a name, as in `1x`: that is
+    //neither a number nor an identifier.
+    fn reject_identifier_suffix(&self) -> Result<(), CalcError>
+    {
+        match self.current_char
+        {
+            Some(c) if is_identifier_char(c) => Err(CalcError::UnexpectedChar(c, self.pos)),
+            _ => Ok(()),
+        }
+    }
+
+    //A template placeholder, `$` followed by a number from 1 up.
+    fn placeholder(&mut self) -> Result<Token, CalcError>
+    {
+        let start = self.pos;
+        self.advance();
+        match self.digits().parse()
+        {
+            Ok(index) if index > 0 => Ok(Token::Placeholder(index)),
+            _ => Err(CalcError::UnexpectedChar('$', start)),
+        }
+    }
+
+    //Return an identifier (a function, constant or variable name) consumed from the input.
+    //Identifiers start with a letter or underscore and may continue with
+    //letters, digits and underscores (`log2`, `total_sum`, `_tmp`).
+    fn identifier(&mut self) -> String {
+        let mut result = String::new();
+        while self.current_char.is_some() && is_identifier_char(self.current_char.unwrap()){
+            result.push(self.current_char.unwrap());
+            self.advance();
+        }
+        result
+    }
+
+    //The operator `spelling` is an alias for, if any.
+    //
+    //        A word such as `x` is only the operator right after something
+    //        that ends an operand, so `3 x 4` and `(1 + 2) x y` multiply but
+    //        the `x` in `x + 1` or `f(x)` is still a variable. An alias that
+    //        isn't a word, like `×`, is always the operator.
+    fn alias(&self, spelling: &str) -> Option<Token>
+    {
+        let word = spelling.chars().all(is_identifier_char);
+        if word && !self.after_operand{
+            return None;
+        }
+        self.aliases.iter().find(|(alias, _)| alias == spelling).map(|(_, operator)| operator.clone())
+    }
+
+    //The character after the current one, if any.
+    fn peek(&self) -> Option<char>
+    {
+        self.text.get(self.pos + 1).copied()
+    }
+
+    //Consume a one-character operator, or a two-character one if it is
+    //followed by `second` (`<` or `<=`).
+    fn one_or_two(&mut self, second: char, single: Token, double: Token) -> Token
+    {
+        self.advance();
+        if self.current_char == Some(second){
+            self.advance();
+            return double;
+        }
+        single
+    }
+
+    //Lexical analyzer (also known as scanner or tokenizer)
+    //
+    //        This method is responsible for breaking a sentence
+    //        apart into tokens. One token at a time.
+    pub fn get_next_token(&mut self) -> Result<Token, CalcError>
+    {
+        let token = self.next_token()?;
+        self.after_operand = matches!(token,
+            Token::Integer(_) | Token::Float(_) | Token::Bool(_) | Token::Identifier(_)
+            | Token::Placeholder(_) | Token::At | Token::Rparen | Token::Percent);
+        if let Some(trace) = &mut self.trace{
+            //A broken trace shouldn't stop the evaluation.
+            let _ = writeln!(trace, "lexer: {:?} at {}", token, self.token_start);
+        }
+        if let (Some(recorded), false) = (&mut self.recorded, token == Token::Eof){
+            recorded.push(token.clone());
+        }
+        Ok(token)
+    }
+
+    fn next_token(&mut self) -> Result<Token, CalcError>
+    {
+        while self.current_char.is_some()
+        {
+            if self.current_char.unwrap().is_whitespace(){
+                self.skip_whitespaces();
+                continue;
+            }
+
+            self.token_start = self.pos;
+
+            if self.current_char.unwrap().is_ascii_digit(){
+                return self.number();
+            }
+
+            if self.current_char.unwrap().is_alphabetic() || self.current_char == Some('_'){
+                return Ok(match self.identifier().as_str()
+                {
+                    "true" => Token::Bool(true),
+                    "false" => Token::Bool(false),
+                    "of" => Token::Of,
+                    "if" => Token::If,
+                    "then" => Token::Then,
+                    "else" => Token::Else,
+                    name => self.alias(name).unwrap_or_else(|| Token::Identifier(String::from(name))),
+                });
+            }
+
+            match self.current_char
+            {
+                Some('+') => {self.advance(); return Ok(Token::Plus)},
+                Some('-') => {self.advance(); return Ok(Token::Minus)},
+                Some('*') => {self.advance(); return Ok(Token::Mul)},
+                Some('/') => {self.advance(); return Ok(Token::Div)},
+                Some('^') => {self.advance(); return Ok(Token::Power)},
+                Some('%') => {self.advance(); return Ok(Token::Percent)},
+                Some('(') => {self.advance(); return Ok(Token::Lparen)},
+                Some(')') => {self.advance(); return Ok(Token::Rparen)},
+                Some(',') => {self.advance(); return Ok(Token::Comma)},
+                Some(';') => {self.advance(); return Ok(Token::Semicolon)},
+                Some('@') => {self.advance(); return Ok(Token::At)},
+                Some('$') => return self.placeholder(),
+                Some('=') => return Ok(self.one_or_two('=', Token::Assign, Token::Equal)),
+                Some('<') => return Ok(self.one_or_two('=', Token::Less, Token::LessEqual)),
+                Some('>') => return Ok(self.one_or_two('=', Token::Greater, Token::GreaterEqual)),
+                Some('!') => return Ok(self.one_or_two('=', Token::Not, Token::NotEqual)),
+                Some('&') if self.peek() == Some('&') => {self.advance(); self.advance(); return Ok(Token::And)},
+                Some('|') if self.peek() == Some('|') => {self.advance(); self.advance(); return Ok(Token::Or)},
+                Some(c) => match self.alias(&c.to_string())
+                {
+                    Some(operator) => {self.advance(); return Ok(operator)},
+                    None => return Err(CalcError::UnexpectedChar(c, self.pos)),
+                },
+                None => unreachable!("the loop only runs while there is a current char"),
+            }
+        }
+
+        self.token_start = self.pos;
+        Ok(Token::Eof)
+    }
+
+    //Log every token produced from now on to `writer`, one per line:
+    //`lexer: Integer(1) at 0`.
+    pub fn trace_to(&mut self, writer: Box<dyn Write>)
+    {
+        self.trace = Some(writer);
+    }
+
+    pub fn token_start(&self) -> usize
+    {
+        self.token_start
+    }
+
+    //Step over the current character, so lexing can go on past one it
+    //rejected.
+    pub fn skip_char(&mut self)
+    {
+        if self.current_char.is_some(){
+            self.advance();
+        }
+    }
+
+    pub fn create_lexer(text: String) -> Lexer{
+        let text: Vec<char> = text.chars().collect();
+        let init_char = text.first().copied();
+        Lexer{
+            text,
+            pos: 0,
+            current_char: init_char,
+            token_start: 0,
+            time_literals: false,
+            recorded: None,
+            group_separator: None,
+            trace: None,
+            aliases: Vec::new(),
+            after_operand: false
+        }
+    }
+
+    //A lexer for the number syntax chosen in `options`.
+    pub fn create_lexer_with_options(text: String, options: &Options) -> Lexer{
+        let mut lexer = Lexer::create_lexer(text);
+        lexer.group_separator = options.group_separator.map(|separator| separator.char());
+        lexer.aliases = options.operator_aliases.clone();
+        if options.trace_tokens{
+            lexer.trace_to(Box::new(io::stderr()));
+        }
+        lexer
+    }
+
+    //A lexer that also reads `H:MM:SS` time literals.
+    pub fn create_time_lexer(text: String) -> Lexer{
+        let mut lexer = Lexer::create_lexer(text);
+        lexer.time_literals = true;
+        lexer
+    }
+
+    //Keep a copy of every token produced from now on, so the input can be
+    //reassembled without lexing it again.
+    pub fn record_tokens(&mut self)
+    {
+        self.recorded = Some(Vec::new());
+    }
+
+    //The tokens kept since `record_tokens`.
+    pub fn take_recorded(&mut self) -> Vec<Token>
+    {
+        self.recorded.take().unwrap_or_default()
+    }
+}
+
+//Split `input` into its tokens, not including the final `Eof`.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, CalcError>
+{
+    tokenize_with_options(input, &Options::default())
+}
+
+//Like `tokenize`, reading numbers as configured in `options`.
+pub fn tokenize_with_options(input: &str, options: &Options) -> Result<Vec<Token>, CalcError>
+{
+    let mut lexer = Lexer::create_lexer_with_options(String::from(input), options);
+    let mut tokens = Vec::new();
+
+    loop {
+        match lexer.get_next_token()?
+        {
+            Token::Eof => return Ok(tokens),
+            token => tokens.push(token),
+        }
+    }
+}
+
+//Join tokens back into source text using their canonical forms.
+pub fn reassemble(tokens: &[Token]) -> String
+{
+    tokens.iter().map(Token::to_str).collect::<Vec<String>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{evaluate, CalcError, Calculator, GroupSeparator, Token, Value};
+
+    use super::tokenize;
+
+    #[test]
+    fn unexpected_characters_are_reported_with_their_position()
+    {
+        assert_eq!(tokenize("1 + #"), Err(CalcError::UnexpectedChar('#', 4)));
+        assert_eq!(evaluate("1 + #").unwrap_err().to_string(), "unexpected character '#' at position 4");
+        //Positions count characters, not bytes.
+        assert_eq!(tokenize("é + ~"), Err(CalcError::UnexpectedChar('~', 4)));
+    }
+
+    #[test]
+    fn identifiers_may_contain_digits_and_underscores()
+    {
+        for name in ["x1", "total_sum", "_tmp"]{
+            assert_eq!(tokenize(name), Ok(vec![Token::Identifier(String::from(name))]));
+        }
+        //A name can't start with a digit.
+        assert_eq!(tokenize("1x"), Err(CalcError::UnexpectedChar('x', 1)));
+    }
+
+    #[test]
+    fn hexadecimal_floats()
+    {
+        for (input, expected) in [("0x1.8p1", 3.0), ("0x1p-2", 0.25), ("0xAp0", 10.0), ("0x.8p1", 1.0), ("0x1.8", 1.5)]{
+            assert_eq!(tokenize(input), Ok(vec![Token::Float(expected)]), "{}", input);
+        }
+        for input in ["0x1p", "0x1p+", "0x1p-"]{
+            assert_eq!(tokenize(input), Err(CalcError::MalformedNumber(String::from(input))), "{}", input);
+        }
+    }
+
+    #[test]
+    fn any_whitespace_separates_tokens()
+    {
+        let expected = Ok(vec![Token::Integer(1), Token::Plus, Token::Integer(2)]);
+        for input in ["1+2", "1\t+\t2", "1    +   2", "1\u{a0}+\u{2003}2", " \t 1+2\u{3000}", "1\n+\r\n2"]{
+            assert_eq!(tokenize(input), expected, "{:?}", input);
+        }
+        for blank in ["", " ", "\t\u{2009}\n"]{
+            assert_eq!(evaluate(blank), Err(CalcError::EmptyExpression), "{:?}", blank);
+        }
+    }
+
+    #[test]
+    fn digits_can_be_grouped_in_threes()
+    {
+        let mut calculator = Calculator::builder().group_separator(GroupSeparator::Apostrophe).build();
+        assert_eq!(calculator.eval("1'000'000").unwrap().into_value(), Some(Value::Int(1_000_000)));
+        assert_eq!(calculator.eval("12'345.5 + 1").unwrap().into_value(), Some(Value::Float(12_346.5)));
+        for malformed in ["1'00", "1234'567", "1'0000"]{
+            assert_eq!(calculator.eval(malformed), Err(CalcError::MalformedNumber(String::from(malformed))));
+        }
+        assert_eq!(calculator.eval("1'000'"), Err(CalcError::UnexpectedChar('\'', 5)));
+
+        //Without the option an apostrophe is just an unexpected character.
+        assert_eq!(evaluate("1'000"), Err(CalcError::UnexpectedChar('\'', 1)));
+    }
+
+    #[test]
+    fn word_aliases_are_operators_only_after_an_operand()
+    {
+        let mut calculator = Calculator::builder().operator_alias("x", Token::Mul).build();
+        let mut value = |input: &str| calculator.eval(input).unwrap().into_value();
+        assert_eq!(value("3 x 4"), Some(Value::Int(12)));
+        assert_eq!(value("x = 2"), Some(Value::Int(2)));
+        assert_eq!(value("x + 1"), Some(Value::Int(3)));
+        assert_eq!(value("(1 + 1) x x"), Some(Value::Int(4)));
+
+        //Without the alias `x` is only ever a name.
+        let mut calculator = Calculator::new();
+        calculator.eval("x = 5").unwrap();
+        assert_eq!(calculator.eval("x + 1").unwrap().into_value(), Some(Value::Int(6)));
+        assert!(matches!(calculator.eval("3 x 4"), Err(CalcError::UnexpectedToken{ found: Token::Identifier(_), .. })));
+    }
+}
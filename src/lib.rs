@@ -0,0 +1,257 @@
+//Simple expression calculator.
+//
+//        `evaluate` computes a single expression; `Calculator` keeps
+//        variables and history across inputs and can snapshot them.
+//        `parse` exposes the syntax tree the evaluator works on, and
+//        `metrics` measures it.
+//
+//        The modules are private; the public API is exactly what this file
+//        defines and re-exports below. `evaluate`, `parse`, `Value` and
+//        `CalcError` are the core of it, and tests/public_api.rs uses the
+//        API the way a dependent crate would.
+//
+//        What a release without a major version bump may change: new
+//        items, new variants of `Value`, `CalcError`, `Token` and `Ast`,
+//        new fields of `Options`, `Metrics`, `OperatorInfo`, `EvalOutcome`,
+//        `Snapshot` and `HistoryEntry`, new entries in `BUILTINS`,
+//        `CONSTANTS` and `operators()`, and the exact text of error
+//        messages, notes, `render_tree` and the token trace. Those enums
+//        and structs are `#[non_exhaustive]`, so the compiler holds
+//        dependent crates to this: a `match` on one of the enums needs a
+//        `_` arm, and the structs are built with `Options::default()`,
+//        `Metrics::default()` or their `create_*` functions instead of
+//        struct literals. The lower-level items (`tokenize`, `reassemble`,
+//        `round_trips`, `validate` and the tables) are there for tools
+//        built on the calculator and follow the same rules.
+
+mod ast;
+mod balance;
+#[cfg(feature = "bignum")]
+mod bignum;
+mod calculator;
+mod duration;
+mod environment;
+mod error;
+mod fast;
+mod functions;
+mod interpreter;
+mod lexer;
+mod metrics;
+mod operators;
+mod options;
+mod parser;
+mod random;
+mod rpn;
+mod snapshot;
+mod template;
+mod token;
+mod value;
+
+pub use ast::{parenthesize, render_tree, to_prefix, to_rpn, validate, Ast};
+pub use balance::check_balanced;
+pub use calculator::{Calculator, CalculatorBuilder};
+pub use duration::{evaluate_time, format_duration};
+pub use environment::NativeFunction;
+pub use error::CalcError;
+pub use fast::evaluate_fast;
+pub use functions::{Arity, Builtin, BUILTINS, CONSTANTS};
+pub use lexer::{reassemble, tokenize};
+pub use metrics::{metrics, Metrics};
+pub use operators::{operators, Associativity, OperatorInfo};
+pub use options::{Angle, Base, GroupSeparator, Options, Rounding};
+pub use rpn::evaluate_rpn;
+pub use snapshot::{HistoryEntry, Snapshot};
+pub use template::Template;
+pub use token::Token;
+pub use value::{EvalOutcome, StatementResult, Value};
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use environment::Environment;
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+
+//Parse `input` (an expression or an assignment) into a syntax tree.
+pub fn parse(input: &str) -> Result<Ast, CalcError>
+{
+    let mut lexer = Lexer::create_lexer(String::from(input));
+    Parser::create_parser(&mut lexer)?.parse()
+}
+
+//Check that lexing `input` and reassembling its tokens yields text that
+//parses to the same tree, guarding against lexer/serialization mismatches.
+pub fn round_trips(input: &str) -> Result<bool, CalcError>
+{
+    let reassembled = reassemble(&tokenize(input)?);
+    Ok(parse(input)? == parse(&reassembled)?)
+}
+
+//The variables `input` reads that have to be supplied before it can be
+//evaluated: `x + 2*y - x` gives ["x", "y"].
+pub fn free_variables(input: &str) -> Result<Vec<String>, CalcError>
+{
+    Ok(ast::free_variables(&parse(input)?))
+}
+
+//Evaluate an already parsed expression with `variables` defined, so a
+//host can parse once and evaluate many times:
+//
+//        let ast = parse("x * x + 1")?;
+//        for x in 0..10 {
+//            let bindings = HashMap::from([(String::from("x"), Value::Int(x))]);
+//            plot(x, eval_ast_with(&ast, &bindings)?);
+//        }
+//
+//        Assignments in `ast` only change this evaluation's copy of the
+//        variables.
+pub fn eval_ast_with(ast: &Ast, variables: &HashMap<String, Value>) -> Result<Value, CalcError>
+{
+    let options = Options::default();
+    let mut env = Environment::create_environment();
+    env.variables = variables.clone();
+    Interpreter::create_interpreter(&mut env, &options).visit(ast)
+}
+
+//Evaluate `input` in a fresh calculator with no variables defined.
+pub fn evaluate(input: &str) -> Result<EvalOutcome, CalcError>
+{
+    evaluate_with_options(input, &Options::default())
+}
+
+//Evaluate a comma-separated list of expressions in a fresh calculator:
+//`1+1, 2*2, 3^2` gives [2, 4, 9].
+pub fn evaluate_list(input: &str) -> Result<Vec<Value>, CalcError>
+{
+    Calculator::new().eval_list(input)
+}
+
+//Evaluate `input` in a fresh calculator, also reporting whether any step,
+//not only the last, lost information: an integer division that dropped
+//a remainder, an integer too large to convert to a float exactly, or
+//finite numbers overflowing to infinity.
+//
+//        evaluate_checked("1 / 2.0 ^ 1024")  ->  Ok((Float(0.0), true))
+//
+//        Integer overflow never wraps around (it is an error, or exact
+//        with the `bignum` feature), so it can't make a result lossy.
+pub fn evaluate_checked(input: &str) -> Result<(Value, bool), CalcError>
+{
+    let mut calculator = Calculator::new();
+    match calculator.eval(input)?.into_value()
+    {
+        Some(value) => Ok((value, calculator.lossy())),
+        None => Err(CalcError::TypeError(String::from("a function definition has no value"))),
+    }
+}
+
+//Like `evaluate`, failing with `CalcError::Timeout` instead of running
+//longer than `limit`, for hosts evaluating untrusted input:
+//
+//        evaluate_with_timeout("sum(i, 1, 999999, sum(j, 1, 999999, i * j))", Duration::from_millis(100))
+//
+//        gives up after about 100ms rather than running for hours.
+pub fn evaluate_with_timeout(input: &str, limit: Duration) -> Result<EvalOutcome, CalcError>
+{
+    evaluate_with_options(input, &Options{ time_limit: Some(limit), ..Options::default() })
+}
+
+//Like `evaluate`, with explicit settings such as the maximum input length.
+pub fn evaluate_with_options(input: &str, options: &Options) -> Result<EvalOutcome, CalcError>
+{
+    outcome(&mut Calculator::with_options(options.clone()), input)
+}
+
+//Evaluate `input` in `calculator` and report it as `evaluate` does.
+fn outcome(calculator: &mut Calculator, input: &str) -> Result<EvalOutcome, CalcError>
+{
+    let value = match calculator.eval(input)?.into_value()
+    {
+        Some(value) => value,
+        None => return Err(CalcError::TypeError(String::from("a function definition has no value"))),
+    };
+
+    Ok(EvalOutcome{
+        value,
+        warnings: calculator.notes().to_vec(),
+        expression: String::from(calculator.expression()),
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    use crate::{CalcError, Calculator, Value};
+
+    use super::{eval_ast_with, evaluate_checked, evaluate_list, evaluate_with_timeout, outcome, parse, round_trips};
+
+    #[test]
+    fn the_outcome_comes_from_a_single_lex()
+    {
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let mut calculator = Calculator::builder().trace_to(trace.clone()).build();
+        let outcome = outcome(&mut calculator, "7/2").unwrap();
+        assert_eq!(outcome.expression, "7 / 2");
+        assert_eq!(outcome.value, Value::Int(3));
+        //One line per token, `Eof` included.
+        assert_eq!(String::from_utf8(trace.borrow().clone()).unwrap().lines().count(), 4);
+    }
+
+    #[test]
+    fn reassembled_tokens_parse_to_the_same_tree()
+    {
+        for input in ["2+3*4", "-(1 - 2) ^ -3", "x = max(1, 2.5, y)", "f(a, b) = if a>=b then a else b", "10% of 200", "!true || 1 != 2", "0x1F + 0x1.8p1"]{
+            assert_eq!(round_trips(input), Ok(true), "{}", input);
+        }
+        assert!(round_trips("1 +").is_err());
+    }
+
+    #[test]
+    fn lists_give_one_value_per_expression()
+    {
+        assert_eq!(evaluate_list("1+1, 2*2, 3^2"), Ok(vec![Value::Int(2), Value::Int(4), Value::Int(9)]));
+        assert_eq!(evaluate_list("max(4, 5), 0.5"), Ok(vec![Value::Int(5), Value::Float(0.5)]));
+        assert_eq!(evaluate_list("7"), Ok(vec![Value::Int(7)]));
+        assert_eq!(evaluate_list("1,"), Err(CalcError::MissingOperand(String::from(","))));
+    }
+
+    #[test]
+    fn any_lossy_step_is_flagged()
+    {
+        assert_eq!(evaluate_checked("(7 / 2) + 1"), Ok((Value::Int(4), true)));
+        assert_eq!(evaluate_checked("7 / 2 * 2.0"), Ok((Value::Float(6.0), true)));
+        assert_eq!(evaluate_checked("1 / 2.0 ^ 1024 + 1"), Ok((Value::Float(1.0), true)));
+        assert_eq!(evaluate_checked("9007199254740993 + 0.5"), Ok((Value::Float(9007199254740992.0), true)));
+
+        assert_eq!(evaluate_checked("1.5 * 2 + 8 / 2"), Ok((Value::Float(7.0), false)));
+        assert_eq!(evaluate_checked("sqrt(16) - 1"), Ok((Value::Float(3.0), false)));
+    }
+
+    #[test]
+    fn slow_evaluations_time_out()
+    {
+        let limit = Duration::from_millis(100);
+        let started = Instant::now();
+        assert_eq!(evaluate_with_timeout("sum(i, 1, 999999, sum(j, 1, 999999, i * j))", limit), Err(CalcError::Timeout(limit)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        assert_eq!(evaluate_with_timeout("sum(i, 1, 100, i)", limit).unwrap().value, Value::Int(5050));
+    }
+
+    #[test]
+    fn a_parsed_tree_evaluates_with_each_binding()
+    {
+        let ast = parse("x * x").unwrap();
+        for (x, square) in [(2, 4), (3, 9), (4, 16)]{
+            let bindings = HashMap::from([(String::from("x"), Value::Int(x))]);
+            assert_eq!(eval_ast_with(&ast, &bindings), Ok(Value::Int(square)));
+        }
+        assert_eq!(eval_ast_with(&ast, &HashMap::new()), Err(CalcError::UnknownVariable(String::from("x"))));
+    }
+}
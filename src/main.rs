@@ -1,202 +1,546 @@
-use std::io;
-use std::io::ErrorKind;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::process;
+use std::time::SystemTime;
 
-#[derive(PartialEq)]
-enum Token
-{
-    Integer(i64),
-    Plus,
-    Minus,
-    Mul,
-    Div,
-    Lparen,
-    Rparen,
-    Eof
-}
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod transcript;
 
-struct Lexer
+use rust_calculator::{evaluate_time, operators, parenthesize, parse, render_tree, to_prefix, to_rpn, Associativity, Base, Calculator, OperatorInfo, StatementResult, BUILTINS, CONSTANTS};
+
+//Command line options.
+//
+//        rust_calculator                          start the interactive prompt
+//        rust_calculator --file sheet.txt         evaluate every line of sheet.txt
+//        rust_calculator --file sheet.txt --interactive
+//                                                 evaluate sheet.txt, then start the
+//                                                 prompt with its variables defined
+//        --no-rc                                  don't load ~/.calcrc at startup
+//        --explain                                show how each expression was grouped
+//        --echo                                   print each expression with its result
+//        --strict                                 reject integer divisions with a remainder
+//        --no-color                               print results and errors without colors
+//        --copy                                   also copy each result to the clipboard
+//                                                 (needs the `clipboard` feature)
+struct Args
 {
-    text: String,
-    pos: usize,
-    current_char: Option<char>
+    file: Option<String>,
+    interactive: bool,
+    load_rc: bool,
+    explain: bool,
+    echo: bool,
+    strict: bool,
+    no_color: bool,
+    #[cfg(feature = "clipboard")]
+    copy: bool
 }
 
-impl Lexer
+//Parse the arguments after the program name.
+fn parse_args(arguments: impl IntoIterator<Item = String>) -> Result<Args, String>
 {
-    //Advance the `pos` pointer and set the `current_char` variable.
-    fn advance(&mut self)
-    {
-        self.pos += 1;
-        if self.pos > self.text.len() - 1
-        {
-            self.current_char = None;
-        }
-        else
+    let mut args = Args{
+        file: None,
+        interactive: false,
+        load_rc: true,
+        explain: false,
+        echo: false,
+        strict: false,
+        no_color: false,
+        #[cfg(feature = "clipboard")]
+        copy: false,
+    };
+
+    let mut iter = arguments.into_iter();
+    while let Some(arg) = iter.next(){
+        match arg.as_str()
         {
-            self.current_char = self.text.chars().nth(self.pos)
+            "--file" => args.file = Some(iter.next().ok_or("--file expects a path")?),
+            "--interactive" => args.interactive = true,
+            "--no-rc" => args.load_rc = false,
+            "--explain" => args.explain = true,
+            "--echo" => args.echo = true,
+            "--strict" => args.strict = true,
+            "--no-color" => args.no_color = true,
+            #[cfg(feature = "clipboard")]
+            "--copy" => args.copy = true,
+            #[cfg(not(feature = "clipboard"))]
+            "--copy" => return Err(String::from("--copy needs a build with `--features clipboard`")),
+            _ => return Err(format!("unknown argument '{}'", arg)),
         }
     }
 
-    fn skip_whitespaces(&mut self)
-    {
-        while self.current_char != None && self.current_char.unwrap().is_whitespace(){
-            self.advance();
-        }
-    }
+    Ok(args)
+}
 
-    //Return a (multidigit) integer consumed from the input.
-    fn integer(&mut self) -> i64 {
-        let mut result = String::new();
-        while self.current_char != None && self.current_char.unwrap().is_ascii_digit(){
-            result.push(self.current_char.unwrap());
-            self.advance();
-        }
-        return result.parse().unwrap();
-    }
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
 
-    //Lexical analyzer (also known as scanner or tokenizer)
-    //
-    //        This method is responsible for breaking a sentence
-    //        apart into tokens. One token at a time.
-    fn get_next_token(&mut self) -> Result<Token, ErrorKind>
-    {
-        while self.current_char != None
-        {
-            if self.current_char.unwrap().is_whitespace(){
-                self.skip_whitespaces();
-                continue;
-            }
+//Whether to color the output: not with `--no-color`, not when NO_COLOR
+//is set to anything but the empty string (https://no-color.org), and
+//only when printing to a terminal rather than a file or pipe.
+fn use_color(no_color_flag: bool, no_color_env: Option<OsString>, is_terminal: bool) -> bool
+{
+    let no_color_env = no_color_env.is_some_and(|value| !value.is_empty());
+    !no_color_flag && !no_color_env && is_terminal
+}
 
-            if self.current_char.unwrap().is_ascii_digit(){
-                return Ok(Token::Integer(self.integer()));
-            }
+//Largest denominator `:frac` looks for unless given one.
+const DEFAULT_DENOMINATOR: u64 = 1000;
 
-            match self.current_char
-            {
-                Some('+') => {self.advance(); return Ok(Token::Plus)},
-                Some('-') => {self.advance(); return Ok(Token::Minus)},
-                Some('*') => {self.advance(); return Ok(Token::Mul)},
-                Some('/') => {self.advance(); return Ok(Token::Div)},
-                Some('(') => {self.advance(); return Ok(Token::Lparen)},
-                Some(')') => {self.advance(); return Ok(Token::Rparen)},
-                _ => return Err(ErrorKind::InvalidData),
-            }
-        }
+//Prompt commands, as listed by `:help`.
+const COMMANDS: &[(&str, &str)] = &[
+    (":help", "show this help"),
+    (":quiet", "toggle echoing of assignments"),
+    (":notes", "toggle notes such as integer divisions that dropped a remainder"),
+    (":frac [<n>]", "toggle showing floats as fractions with denominators up to <n> (1000)"),
+    (":base <n> <x>", "show the result of expression <x> in base <n> (2, 8, 10 or 16)"),
+    (":ast <expr>", "print the parse tree of <expr> as an indented outline"),
+    (":rpn <expr>", "print <expr> in reverse Polish notation (2 3 4 * + for 2 + 3 * 4)"),
+    (":prefix <expr>", "print <expr> in Polish notation (+ 2 * 3 4 for 2 + 3 * 4)"),
+    (":m+ / :m-", "add / subtract the last result to / from memory"),
+    (":mr / :mc", "show / clear memory (expressions can read it as `mem`)"),
+    (":seed <n>", "restart random() / randint(a, b) from seed <n>"),
+    (":reset", "forget all variables, functions, history and memory, and turn :quiet and :notes off"),
+    (":time <expr>", "evaluate <expr> over H:MM:SS times (1:30:00 + 0:45:00)"),
+    (":save <path>", "write this session's inputs and results to <path> (:save -t <path> adds timestamps)"),
+];
 
-        return Ok(Token::Eof);
-    }
+//One `:help` line for operators sharing a precedence level:
+//`* /   multiplication, division (left-associative)`.
+fn operator_line(level: &[OperatorInfo]) -> String
+{
+    let symbols = level.iter().map(|operator| operator.symbol).collect::<Vec<&str>>().join(" ");
+    let descriptions = level.iter().map(|operator| operator.description).collect::<Vec<&str>>().join(", ");
+    let grouping = match (level[0].arity, level[0].associativity)
+    {
+        (1, Associativity::Left) => "postfix",
+        (1, _) => "prefix",
+        (_, Associativity::Left) => "left-associative",
+        (_, Associativity::Right) => "right-associative",
+        (_, Associativity::None) => "doesn't chain",
+    };
+    format!("  {:<16}{} ({})", symbols, descriptions, grouping)
+}
 
-    fn create_lexer(text: String) -> Lexer{
-        let init_char = text.chars().nth(0);
-        Lexer{
-            text,
-            pos: 0,
-            current_char: init_char
-        }
+//Text for `:help`. Operators, functions and constants come from the
+//library's tables, so new ones show up without editing this.
+fn help() -> String
+{
+    let mut lines = vec![String::from("Operators, from the tightest binding:"), format!("  {:<16}{}", "( )", "grouping")];
+    let operators = operators();
+    for level in operators.chunk_by(|a, b| a.precedence == b.precedence){
+        lines.push(operator_line(level));
     }
+
+    lines.push(String::from("Functions:"));
+    lines.extend(BUILTINS.iter().map(|builtin| {
+        format!("  {:<16}{}", format!("{}({})", builtin.name, builtin.params), builtin.description)
+    }));
+
+    lines.push(String::from("Constants:"));
+    lines.extend(CONSTANTS.iter().map(|(name, value)| format!("  {:<16}{}", name, value)));
+
+    lines.push(String::from("Commands:"));
+    lines.extend(COMMANDS.iter().map(|(command, description)| format!("  {:<16}{}", command, description)));
+    lines.join("\n")
+}
+
+//An expression followed by its result, as printed with `--echo`: `2 + 3 = 5`.
+fn echoed(input: &str, result: &str) -> String
+{
+    format!("{} = {}", input.trim(), result)
 }
 
-struct Interpreter<'a>
+//Interactive prompt state: the calculator session plus display toggles.
+struct Repl
 {
-    lexer: &'a mut Lexer,
-    current_token: Token
+    calculator: Calculator,
+    //Don't echo assignments (toggled with `:quiet`).
+    quiet: bool,
+    //Print notes such as truncated integer divisions (toggled with `:notes`).
+    notes: bool,
+    //Show how expressions were grouped (`--explain`).
+    explain: bool,
+    //Print expressions along with their results (`--echo`).
+    echo: bool,
+    //Copy each result to the clipboard (`--copy`).
+    #[cfg(feature = "clipboard")]
+    copy: bool,
+    //Print results in green and errors in red.
+    color: bool,
+    //Every evaluated line and its output, for `:save`.
+    transcript: Vec<transcript::Entry>
 }
 
-impl<'a> Interpreter<'a>
+impl Repl
 {
-    fn eat(&mut self)
+    fn create_repl(calculator: Calculator) -> Repl
     {
-        let res = self.lexer.get_next_token();
+        Repl{
+            calculator,
+            quiet: false,
+            notes: false,
+            explain: false,
+            echo: false,
+            #[cfg(feature = "clipboard")]
+            copy: false,
+            color: false,
+            transcript: Vec::new(),
+        }
+    }
 
-        match res
+    //Text to print for a successful statement, if any. Assignments echo
+    //as `x = 5` unless quiet mode is on; expressions print the bare result,
+    //`2 + 3 = 5` with `--echo`, or `2 + 3 * 4 grouped as 2 + (3 * 4) = 14`
+    //with `--explain`.
+    fn render(&self, input: &str, result: &StatementResult) -> Option<String>
+    {
+        match result
         {
-            Ok(token) => self.current_token = token,
-            Err(_e) => panic!("Error parsing the string (probably an invalid character)"),
+            StatementResult::Assignment(_, _) if self.quiet => None,
+            StatementResult::Expression(_) if self.explain => {
+                let grouped = parse(input).map(|node| parenthesize(&node)).unwrap_or_default();
+                Some(format!("{} grouped as {} = {}", input, grouped, self.calculator.format(result)))
+            }
+            StatementResult::Expression(_) if self.echo => Some(echoed(input, &self.calculator.format(result))),
+            result => Some(self.calculator.format(result)),
         }
     }
 
-    //factor : INTEGER | LPAREN expr RPAREN
-    fn factor(&mut self) -> i64
+    //`text` in `color`, if colors are on.
+    fn paint(&self, text: String, color: &str) -> String
     {
-        match self.current_token
-        {
-            Token::Integer(integer) => {self.eat(); return integer},
-            Token::Lparen => {self.eat(); let result = self.expr(); self.eat(); return result}
-            _ => panic!("Error in the factor rule")
+        if self.color { format!("{}{}{}", color, text, RESET) } else { text }
+    }
+
+    //Copy the value of `result` to the clipboard if `--copy` was given.
+    #[cfg(feature = "clipboard")]
+    fn copy_result(&self, result: &StatementResult)
+    {
+        if !self.copy{
+            return;
+        }
+        if let Some(value) = result.value(){
+            if let Err(e) = clipboard::copy(&value.format(self.calculator.options())){
+                eprintln!("warning: {}", e);
+            }
         }
     }
 
-    //term : factor ((MUL | DIV) factor)*
-    fn term(&mut self) -> i64
+    //Handle a `:command` line, returning the text to print.
+    fn command(&mut self, line: &str) -> String
     {
-        let mut result = self.factor();
+        let (command, argument) = match line.split_once(' ')
+        {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
 
-        while self.current_token == Token::Mul || self.current_token == Token::Div{
-            match self.current_token
+        match command
+        {
+            ":ast" => match parse(argument)
+            {
+                Ok(node) => render_tree(&node).trim_end().to_string(),
+                Err(e) => format!("Error: {}", e),
+            },
+            ":rpn" | ":prefix" => match parse(argument)
             {
-                Token::Mul => {self.eat(); result *= self.factor();},
-                Token::Div => {self.eat(); result /= self.factor();},
-                _ => panic!("Error in term rule")
+                Ok(node) if command == ":rpn" => to_rpn(&node),
+                Ok(node) => to_prefix(&node),
+                Err(e) => format!("Error: {}", e),
+            },
+            ":help" => help(),
+            ":quiet" => {
+                self.quiet = !self.quiet;
+                format!("quiet mode {}", if self.quiet { "on" } else { "off" })
+            }
+            ":notes" => {
+                self.notes = !self.notes;
+                format!("notes {}", if self.notes { "on" } else { "off" })
             }
+            ":frac" => {
+                let limit = match argument
+                {
+                    "" if self.calculator.options().max_denominator.is_some() => None,
+                    "" => Some(DEFAULT_DENOMINATOR),
+                    limit => match limit.parse()
+                    {
+                        Ok(limit) if limit > 0 => Some(limit),
+                        _ => return String::from("Error: :frac expects a positive integer"),
+                    },
+                };
+                self.calculator.fractions(limit);
+                match limit
+                {
+                    Some(limit) => format!("fractions on (denominators up to {})", limit),
+                    None => String::from("fractions off"),
+                }
+            }
+            ":base" => {
+                let (radix, expression) = argument.split_once(' ').unwrap_or((argument, ""));
+                let base = match radix.parse().ok().and_then(Base::from_radix)
+                {
+                    Some(base) => base,
+                    None => return String::from("Error: :base expects a base of 2, 8, 10 or 16 and an expression"),
+                };
+                let mut options = self.calculator.options().clone();
+                options.base = base;
+                match self.calculator.eval(expression)
+                {
+                    Ok(result) => result.format(&options),
+                    Err(e) => format!("Error: {}", e),
+                }
+            }
+            ":m+" | ":m-" => {
+                let memory = if command == ":m+" { self.calculator.memory_add() } else { self.calculator.memory_subtract() };
+                match memory
+                {
+                    Ok(memory) => format!("mem = {}", memory),
+                    Err(e) => format!("Error: {}", e),
+                }
+            }
+            ":mr" => format!("mem = {}", self.calculator.memory()),
+            ":mc" => {self.calculator.memory_clear(); String::from("mem = 0")},
+            ":reset" => {
+                self.calculator.reset();
+                self.quiet = false;
+                self.notes = false;
+                String::from("reset")
+            }
+            ":time" => evaluate_time(argument).unwrap_or_else(|e| format!("Error: {}", e)),
+            ":save" => {
+                let (timestamps, path) = match argument.strip_prefix("-t")
+                {
+                    Some(path) if path.is_empty() || path.starts_with(' ') => (true, path.trim()),
+                    _ => (false, argument),
+                };
+                if path.is_empty(){
+                    return String::from("Error: :save expects a path");
+                }
+                match fs::write(path, transcript::serialize(&self.transcript, timestamps))
+                {
+                    Ok(()) => {
+                        let count = self.transcript.len();
+                        format!("saved {} {} to {}", count, if count == 1 { "line" } else { "lines" }, path)
+                    }
+                    Err(e) => format!("Error: cannot write {}: {}", path, e),
+                }
+            }
+            ":seed" => match argument.parse()
+            {
+                Ok(seed) => {self.calculator.seed(seed); format!("seed = {}", seed)},
+                Err(_e) => String::from("Error: :seed expects a non-negative integer"),
+            },
+            _ => format!("Error: unknown command '{}'", line),
         }
-
-        return result;
     }
 
-    //Arithmetic expression parser / interpreter.
-    //
-    //        calc> 7 + 3 * (10 / (12 / (3 + 1) - 1))
-    //        22
-    //
-    //        expr   : term ((PLUS | MINUS) term)*
-    //        term   : factor ((MUL | DIV) factor)*
-    //        factor : INTEGER | LPAREN expr RPAREN
-    fn expr(&mut self) -> i64
+    //Evaluate one line of input, returning the text to print.
+    fn handle_line(&mut self, line: &str) -> Option<String>
     {
-        let mut result = self.term();
+        let line = line.trim();
+        if line.starts_with(':'){
+            let output = self.command(line);
+            return Some(if output.starts_with("Error:") { self.paint(output, RED) } else { output });
+        }
 
-        while self.current_token == Token::Plus || self.current_token == Token::Minus{
-            match self.current_token
+        //`a = 2; a * 3` prints one line per statement.
+        let mut results = Vec::new();
+        self.calculator.eval_statements(line, |statement, result| results.push((String::from(statement), result)));
+
+        let mut output = Vec::new();
+        for (statement, result) in results{
+            match result
             {
-                Token::Plus => {self.eat(); result += self.term();},
-                Token::Minus => {self.eat(); result -= self.term();},
-                _ => panic!("Error in expr rule")
+                Ok(result) => {
+                    #[cfg(feature = "clipboard")]
+                    self.copy_result(&result);
+                    output.extend(self.render(&statement, &result).map(|text| self.paint(text, GREEN)));
+                }
+                Err(e) => output.push(self.paint(format!("Error: {}", e), RED)),
             }
         }
+        if self.notes{
+            output.extend(self.calculator.notes().iter().map(|note| format!("note: {}", note)));
+        }
 
-        return result;
+        let output = if output.is_empty() { None } else { Some(output.join("\n")) };
+        self.transcript.push(transcript::Entry{
+            input: String::from(line),
+            output: output.clone(),
+            time: SystemTime::now(),
+        });
+        output
     }
 
-    fn create_interpreter(lexer: &mut Lexer) -> Interpreter
+    fn run_file(&mut self, path: &str) -> Result<(), String>
     {
-        let res_token = lexer.get_next_token();
-        let cur_token: Token;
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
 
-        match res_token{
-            Ok(token ) => cur_token = token,
-            Err(_e) => panic!("Error in creating the interpreter"),
+        //Lines are handled as if typed at the prompt, skipping blank lines
+        //and `#` comments like `Calculator::eval_lines`.
+        for line in text.lines().filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#')){
+            if let Some(output) = self.handle_line(line){
+                println!("{}", output);
+            }
         }
 
-        Interpreter{
-            lexer,
-            current_token: cur_token,
+        Ok(())
+    }
+
+    fn run(&mut self)
+    {
+        loop {
+            println!("Please insert the formula you want to calculate. Press CTRL-c to exit");
+            let mut formula = String::new();
+            if io::stdin().read_line(&mut formula).expect("Failed to read line") == 0{
+                break;
+            }
+
+            if let Some(output) = self.handle_line(&formula){
+                println!("{}", output);
+            }
         }
     }
 }
 
+//Evaluate ~/.calcrc, if there is one, so its variables and functions
+//are available from the start. Broken lines only produce a warning.
+fn load_rc(calculator: &mut Calculator)
+{
+    let path = match env::var_os("HOME")
+    {
+        Some(home) => std::path::Path::new(&home).join(".calcrc"),
+        None => return,
+    };
+    let text = match fs::read_to_string(&path)
+    {
+        Ok(text) => text,
+        Err(_e) => return,
+    };
+
+    for (line, e) in calculator.load_definitions(&text){
+        eprintln!("warning: {}:{}: {}", path.display(), line, e);
+    }
+}
+
 fn main() {
-    loop {
-        println!("Please insert the formula you want to calculate. Press CTRL-c to exit");
-        let mut formula = String::new();
-        io::stdin().read_line(&mut formula).expect("Failed to read line");
+    let args = parse_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("rust_calculator: {}", e);
+        process::exit(2);
+    });
+
+    let mut calculator = Calculator::builder().strict(args.strict).build();
+    if args.load_rc{
+        load_rc(&mut calculator);
+    }
+
+    let mut repl = Repl::create_repl(calculator);
+    repl.explain = args.explain;
+    repl.echo = args.echo;
+    repl.color = use_color(args.no_color, env::var_os("NO_COLOR"), io::stdout().is_terminal());
+    #[cfg(feature = "clipboard")]
+    {
+        repl.copy = args.copy;
+    }
+
+    if let Some(path) = &args.file{
+        if let Err(e) = repl.run_file(path){
+            eprintln!("rust_calculator: {}", e);
+            process::exit(1);
+        }
+        if !args.interactive{
+            return;
+        }
+    }
 
-        let mut lexer = Lexer::create_lexer(formula);
+    repl.run();
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::ffi::OsString;
+
+    use rust_calculator::{Calculator, BUILTINS};
+
+    use super::{echoed, help, parse_args, use_color, Args, Repl};
+
+    fn parsed(arguments: &[&str]) -> Result<Args, String>
+    {
+        parse_args(arguments.iter().map(|argument| String::from(*argument)))
+    }
 
-        let mut interpreter = Interpreter::create_interpreter(&mut lexer);
+    #[test]
+    fn help_lists_every_builtin()
+    {
+        let help = help();
+        for builtin in BUILTINS{
+            assert!(help.contains(&format!("  {}({})", builtin.name, builtin.params)), "{} is missing from the help", builtin.name);
+        }
+    }
 
-        let result = interpreter.expr();
+    #[test]
+    fn arguments_set_their_options()
+    {
+        let args = parsed(&["--file", "sheet.txt", "--interactive", "--no-rc", "--strict"]).unwrap();
+        assert_eq!(args.file.as_deref(), Some("sheet.txt"));
+        assert!(args.interactive && !args.load_rc && args.strict && !args.echo);
+
+        assert_eq!(parsed(&["--file"]).err().as_deref(), Some("--file expects a path"));
+        assert_eq!(parsed(&["--verbose"]).err().as_deref(), Some("unknown argument '--verbose'"));
+    }
 
-        println!("{}", result);
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn copy_is_accepted_with_the_clipboard_feature()
+    {
+        assert!(parsed(&["--copy"]).unwrap().copy);
+        assert!(!parsed(&[]).unwrap().copy);
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    #[test]
+    fn copy_needs_the_clipboard_feature()
+    {
+        assert_eq!(parsed(&["--copy"]).err().as_deref(), Some("--copy needs a build with `--features clipboard`"));
+    }
+
+    #[test]
+    fn echoed_expressions_are_followed_by_their_result()
+    {
+        assert_eq!(echoed("2 + 3", "5"), "2 + 3 = 5");
+        assert_eq!(echoed("  sqrt(16)\n", "4.0"), "sqrt(16) = 4.0");
+    }
+
+    #[test]
+    fn color_only_on_a_terminal_without_no_color()
+    {
+        assert!(use_color(false, None, true));
+        assert!(!use_color(false, None, false));
+        assert!(!use_color(true, None, true));
+        assert!(!use_color(false, Some(OsString::from("1")), true));
+        //An empty NO_COLOR doesn't count as set.
+        assert!(use_color(false, Some(OsString::new()), true));
+        assert!(!use_color(false, Some(OsString::new()), false));
+    }
+
+    #[test]
+    fn base_shows_one_result_in_another_base()
+    {
+        let mut repl = Repl::create_repl(Calculator::new());
+        assert_eq!(repl.command(":base 16 255"), "0xff");
+        assert_eq!(repl.command(":base 2 10"), "0b1010");
+        assert_eq!(repl.command(":base 8 8"), "0o10");
+        assert_eq!(repl.command(":base 16 -255"), "-0xff");
+        assert_eq!(repl.command(":base 2 0"), "0b0");
+        assert_eq!(repl.command(":base 3 1"), "Error: :base expects a base of 2, 8, 10 or 16 and an expression");
+        //The calculator's own base is unchanged.
+        let result = repl.calculator.eval("255").unwrap();
+        assert_eq!(repl.calculator.format(&result), "255");
     }
-    //println!("Hello, world!");
 }
@@ -0,0 +1,95 @@
+use crate::ast::{free_variables, Ast};
+
+//Size and shape of an expression, for hosts that want to reject overly
+//complex untrusted input before evaluating it.
+//
+//        For `1 + 2 * (3 - x)`: 3 operators, no function calls, a maximum
+//        depth of 2 (the `-` sits two levels below the outermost `+`) and
+//        1 distinct variable.
+#[derive(PartialEq, Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Metrics
+{
+    //Unary and binary operators, and `if` expressions.
+    pub operators: usize,
+    pub function_calls: usize,
+    //How deeply operators and calls are nested inside one another; 0 for
+    //a single operation (or a lone number).
+    pub max_depth: usize,
+    //Distinct variables that have to be defined for it to evaluate, as
+    //listed by `free_variables`, so constants such as `pi` don't count.
+    pub variables: usize
+}
+
+fn walk(node: &Ast, depth: usize, metrics: &mut Metrics)
+{
+    match node
+    {
+        Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Variable(_) => {}
+        Ast::UnaryOp(_, operand) => {
+            metrics.operators += 1;
+            metrics.max_depth = metrics.max_depth.max(depth);
+            walk(operand, depth + 1, metrics);
+        }
+        Ast::BinOp(left, _, right) => {
+            metrics.operators += 1;
+            metrics.max_depth = metrics.max_depth.max(depth);
+            walk(left, depth + 1, metrics);
+            walk(right, depth + 1, metrics);
+        }
+        Ast::Call(_, args) => {
+            metrics.function_calls += 1;
+            metrics.max_depth = metrics.max_depth.max(depth);
+            for arg in args{
+                walk(arg, depth + 1, metrics);
+            }
+        }
+        Ast::If(condition, then, otherwise) => {
+            metrics.operators += 1;
+            metrics.max_depth = metrics.max_depth.max(depth);
+            for branch in [condition, then, otherwise]{
+                walk(branch, depth + 1, metrics);
+            }
+        }
+        Ast::Assign(_, value) => walk(value, depth, metrics),
+        Ast::FunctionDef(_, _, body) => walk(body, depth, metrics),
+    }
+}
+
+pub fn metrics(ast: &Ast) -> Metrics
+{
+    let mut metrics = Metrics::default();
+    walk(ast, 0, &mut metrics);
+    metrics.variables = free_variables(ast).len();
+    metrics
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::parse;
+
+    use super::{metrics, Metrics};
+
+    fn measure(input: &str) -> Metrics
+    {
+        metrics(&parse(input).unwrap())
+    }
+
+    #[test]
+    fn operators_depth_and_variables_are_counted()
+    {
+        assert_eq!(measure("1 + 2 * (3 - x)"), Metrics{ operators: 3, function_calls: 0, max_depth: 2, variables: 1 });
+        assert_eq!(measure("max(x, sqrt(y)) + x"), Metrics{ operators: 1, function_calls: 2, max_depth: 2, variables: 2 });
+        assert_eq!(measure("42"), Metrics::default());
+    }
+
+    #[test]
+    fn constants_and_bound_names_are_not_variables()
+    {
+        assert_eq!(measure("pi * r").variables, 1);
+        assert_eq!(measure("mem + e + @").variables, 0);
+        assert_eq!(measure("sum(i, 1, n, i ^ 2)").variables, 1);
+        assert_eq!(measure("f(x) = x * y").variables, 1);
+    }
+}
@@ -0,0 +1,176 @@
+//How a chain of operators with the same precedence groups.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Associativity
+{
+    //`1 - 2 - 3` is `(1 - 2) - 3`; also used for postfix operators.
+    Left,
+    //`2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`; also used for prefix operators.
+    Right,
+    //Chains are rejected: `1 < 2 < 3` is an error.
+    None
+}
+
+//One operator of the grammar, as listed by `operators()`.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub struct OperatorInfo
+{
+    pub symbol: &'static str,
+    //Higher binds tighter.
+    pub precedence: u8,
+    pub associativity: Associativity,
+    //1 for prefix and postfix operators, 2 for infix ones.
+    pub arity: usize,
+    pub description: &'static str
+}
+
+impl OperatorInfo
+{
+    pub const fn create_operator(symbol: &'static str, precedence: u8, associativity: Associativity, arity: usize, description: &'static str) -> OperatorInfo
+    {
+        OperatorInfo{ symbol, precedence, associativity, arity, description }
+    }
+}
+
+const fn operator(symbol: &'static str, precedence: u8, associativity: Associativity, arity: usize, description: &'static str) -> OperatorInfo
+{
+    OperatorInfo::create_operator(symbol, precedence, associativity, arity, description)
+}
+
+//Every operator, from the tightest binding to the loosest. This mirrors
+//the grammar in parser.rs; a test parses every pair of neighbouring
+//levels to check that the two agree.
+const OPERATORS: &[OperatorInfo] = &[
+    operator("%", 9, Associativity::Left, 1, "percent"),
+    operator("^", 8, Associativity::Right, 2, "power"),
+    operator("+", 7, Associativity::Right, 1, "plus sign"),
+    operator("-", 7, Associativity::Right, 1, "negation"),
+    operator("!", 7, Associativity::Right, 1, "logical not"),
+    operator("*", 6, Associativity::Left, 2, "multiplication"),
+    operator("/", 6, Associativity::Left, 2, "division"),
+    operator("of", 6, Associativity::Left, 2, "percentage of (20% of 150)"),
+    operator("+", 5, Associativity::Left, 2, "addition"),
+    operator("-", 5, Associativity::Left, 2, "subtraction"),
+    operator("==", 4, Associativity::None, 2, "equal"),
+    operator("!=", 4, Associativity::None, 2, "not equal"),
+    operator("<", 4, Associativity::None, 2, "less than"),
+    operator("<=", 4, Associativity::None, 2, "less than or equal"),
+    operator(">", 4, Associativity::None, 2, "greater than"),
+    operator(">=", 4, Associativity::None, 2, "greater than or equal"),
+    operator("&&", 3, Associativity::Left, 2, "logical and"),
+    operator("||", 2, Associativity::Left, 2, "logical or"),
+    operator("=", 1, Associativity::Right, 2, "assignment"),
+];
+
+//The operators the grammar supports, ordered from the tightest binding
+//to the loosest, for GUIs and documentation generators.
+pub fn operators() -> Vec<OperatorInfo>
+{
+    OPERATORS.to_vec()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::parse;
+
+    use super::{operators, Associativity, OperatorInfo};
+
+    fn infix(symbol: &str) -> OperatorInfo
+    {
+        operators().into_iter().find(|operator| operator.symbol == symbol && operator.arity == 2).unwrap()
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition()
+    {
+        let (plus, times) = (infix("+"), infix("*"));
+        assert!(times.precedence > plus.precedence);
+        assert_eq!(plus.associativity, Associativity::Left);
+        assert_eq!(infix("^").associativity, Associativity::Right);
+
+        let listed = operators();
+        assert!(listed.windows(2).all(|pair| pair[0].precedence >= pair[1].precedence));
+        assert!(listed.iter().position(|operator| *operator == times) < listed.iter().position(|operator| *operator == plus));
+    }
+
+    //`operand` with `operator` applied, or chained to `right` for an
+    //infix operator.
+    fn apply(operator: &OperatorInfo, operand: &str, right: &str) -> String
+    {
+        match (operator.arity, operator.associativity)
+        {
+            (2, _) => format!("{} {} {}", operand, operator.symbol, right),
+            (_, Associativity::Right) => format!("{} {}", operator.symbol, operand),
+            _ => format!("{} {}", operand, operator.symbol),
+        }
+    }
+
+    //Whether `input` parses to the same tree as the explicitly grouped
+    //`grouped`.
+    fn groups_as(input: &str, grouped: &str) -> bool
+    {
+        match (parse(input), parse(grouped))
+        {
+            (Ok(tree), Ok(expected)) => tree == expected,
+            _ => false,
+        }
+    }
+
+    //The table is kept by hand beside the parser, so check it against
+    //what the parser does: for every tighter and looser operator on
+    //adjacent levels, the tighter one groups first.
+    #[test]
+    fn the_parser_groups_operators_as_listed()
+    {
+        let listed = operators();
+        let mut levels: Vec<u8> = listed.iter().map(|operator| operator.precedence).collect();
+        levels.dedup();
+
+        for pair in levels.windows(2){
+            let level = |precedence| listed.iter().filter(move |operator: &&OperatorInfo| operator.precedence == precedence);
+            for tight in level(pair[0]){
+                for loose in level(pair[1]){
+                    let (input, grouped) = match (tight.arity, loose.arity)
+                    {
+                        //`a - b * c` is `a - (b * c)`, `-a * b` is `(-a) * b`
+                        //and `a ^ b%` is `a ^ (b%)`.
+                        (_, 2) => {
+                            let inner = apply(tight, "b", "c");
+                            (apply(loose, "a", &inner), apply(loose, "a", &format!("({})", inner)))
+                        }
+                        //`-a ^ b` is `-(a ^ b)`.
+                        _ => {
+                            let inner = apply(tight, "a", "b");
+                            (apply(loose, &inner, ""), apply(loose, &format!("({})", inner), ""))
+                        }
+                    };
+                    assert!(groups_as(&input, &grouped), "{} should group as {}", input, grouped);
+
+                    //`a * b - c` is `(a * b) - c`.
+                    if tight.arity == 2 && loose.arity == 2 && loose.symbol != "="{
+                        let left = apply(tight, "a", "b");
+                        let input = apply(loose, &left, "c");
+                        let grouped = apply(loose, &format!("({})", left), "c");
+                        assert!(groups_as(&input, &grouped), "{} should group as {}", input, grouped);
+                    }
+                }
+            }
+        }
+
+        //A chain of two infix operators of the same level groups by their
+        //associativity. Assignments can't be parenthesized, so `a = b = c`
+        //is left to the parser's own tests.
+        for first in listed.iter().filter(|operator| operator.arity == 2 && operator.symbol != "="){
+            for second in listed.iter().filter(|operator| operator.arity == 2 && operator.precedence == first.precedence){
+                let input = apply(second, &apply(first, "a", "b"), "c");
+                match first.associativity
+                {
+                    Associativity::Left => assert!(groups_as(&input, &apply(second, &format!("({})", apply(first, "a", "b")), "c")), "{}", input),
+                    Associativity::Right => assert!(groups_as(&input, &apply(first, "a", &format!("({})", apply(second, "b", "c")))), "{}", input),
+                    Associativity::None => assert!(parse(&input).is_err(), "{}", input),
+                }
+            }
+        }
+    }
+}
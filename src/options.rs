@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use crate::error::CalcError;
+use crate::token::Token;
+use crate::value::Value;
+
+//Radix integer results are displayed in.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Base
+{
+    Binary,
+    Octal,
+    Decimal,
+    Hex
+}
+
+impl Base
+{
+    //The base with this radix, if it is one of 2, 8, 10 and 16.
+    pub fn from_radix(radix: u32) -> Option<Base>
+    {
+        match radix
+        {
+            2 => Some(Base::Binary),
+            8 => Some(Base::Octal),
+            10 => Some(Base::Decimal),
+            16 => Some(Base::Hex),
+            _ => None,
+        }
+    }
+}
+
+//Unit the trigonometric functions take (and the inverse ones return).
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Angle
+{
+    Radians,
+    Degrees
+}
+
+//Character that may group the digits of numbers in the input, in threes:
+//`1 000 000` or, Swiss-style, `1'000'000`.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum GroupSeparator
+{
+    Space,
+    Apostrophe
+}
+
+impl GroupSeparator
+{
+    pub fn char(self) -> char
+    {
+        match self
+        {
+            GroupSeparator::Space => ' ',
+            GroupSeparator::Apostrophe => '\'',
+        }
+    }
+}
+
+//How float results are rounded to the configured precision for display.
+//Ties are values exactly halfway, like 2.5 at 0 digits: half-up rounds
+//them away from zero (3, and -3 for -2.5), half-even to the even
+//neighbour (2, and -2 for -2.5).
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Rounding
+{
+    HalfUp,
+    HalfEven,
+    TowardZero,
+    Ceil,
+    Floor
+}
+
+//Settings that control how input is evaluated and results are displayed.
+//
+//        More settings are added over time, so outside this crate start
+//        from `Options::default()` and assign the fields you need:
+//
+//        let mut options = Options::default();
+//        options.base = Base::Hex;
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub struct Options
+{
+    pub base: Base,
+    pub angle: Angle,
+    //Digits shown after the decimal point of float results; `None` prints
+    //the shortest form that reads back as the same float.
+    pub precision: Option<usize>,
+    //How `precision` rounds; evaluation itself is never rounded.
+    pub rounding: Rounding,
+    //Show float results as fractions such as 3/4 when one with at most
+    //this denominator matches; `None` always shows decimals.
+    pub max_denominator: Option<u64>,
+    //Seed for `random()` and `randint()`, so results can be reproduced;
+    //`None` seeds from the clock.
+    pub seed: Option<u64>,
+    //Fail on integer divisions with a remainder, such as 7 / 2, instead
+    //of truncating them.
+    pub strict: bool,
+    //What a variable that was never assigned reads as; `None` makes it an
+    //`UnknownVariable` error.
+    pub undefined_value: Option<Value>,
+    //Accept numbers whose digits are grouped with this separator; `None`
+    //only accepts plain digits.
+    pub group_separator: Option<GroupSeparator>,
+    //Print every token the lexer produces, with its position, to stderr
+    //(or to the writer given to `CalculatorBuilder::trace_to`).
+    pub trace_tokens: bool,
+    //Extra spellings of binary operators, such as ("x", Token::Mul) for
+    //`3 x 4`. A word is only the operator right after a number, a name,
+    //`)` or `%`, so the `x` in `x + 1` is still a variable.
+    pub operator_aliases: Vec<(String, Token)>,
+    //Longest input, in characters, that will be lexed at all. Protects
+    //hosts that evaluate untrusted input from huge strings.
+    pub max_input_len: usize,
+    //How long evaluating one statement may take before it fails with
+    //`Timeout`; `None` never stops it. The clock is checked at every node
+    //evaluated, so a single slow step can overrun the limit a little.
+    pub time_limit: Option<Duration>
+}
+
+impl Default for Options
+{
+    fn default() -> Options
+    {
+        Options{
+            base: Base::Decimal,
+            angle: Angle::Radians,
+            precision: None,
+            rounding: Rounding::HalfEven,
+            max_denominator: None,
+            seed: None,
+            strict: false,
+            undefined_value: None,
+            group_separator: None,
+            trace_tokens: false,
+            operator_aliases: Vec::new(),
+            max_input_len: 10000,
+            time_limit: None,
+        }
+    }
+}
+
+impl Options
+{
+    //Reject `input` if it is longer than `max_input_len` characters.
+    pub fn check_input(&self, input: &str) -> Result<(), CalcError>
+    {
+        //Bytes bound characters from above, so only count when it matters,
+        //and stop counting as soon as the limit is passed.
+        let limit = self.max_input_len;
+        if input.len() > limit && input.chars().take(limit + 1).count() > limit{
+            return Err(CalcError::InputTooLong(limit));
+        }
+        Ok(())
+    }
+}
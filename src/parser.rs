@@ -0,0 +1,673 @@
+use std::collections::{HashSet, VecDeque};
+use std::ops::Range;
+
+use crate::ast::{height, validate, Ast};
+use crate::error::CalcError;
+use crate::functions::{check_arity, find_builtin};
+use crate::lexer::{reassemble, Lexer};
+use crate::token::Token;
+
+//How deeply parentheses, calls, `if`s and prefix operators may nest.
+//The parser recurses through every grammar rule for each `(`, which
+//takes about 18 KB of stack in a debug build, and nested calls are then
+//evaluated recursively too, so this keeps the deepest input to about
+//half of a 2 MB thread stack (the default for spawned threads).
+pub const MAX_DEPTH: usize = 50;
+
+//Tallest expression tree the parser will build. Operator chains such as
+//`1+1+...` are parsed in a loop but evaluated by recursion, about 3.5 KB
+//of stack per level in a debug build.
+pub const MAX_HEIGHT: usize = 250;
+
+//How many of the tokens consumed before a syntax error the error quotes.
+pub const RECENT_TOKENS: usize = 3;
+
+//Consume `current`, remembering it among the most recent tokens.
+pub fn remember(recent: &mut VecDeque<Token>, current: Token)
+{
+    if recent.len() == RECENT_TOKENS{
+        recent.pop_front();
+    }
+    recent.push_back(current);
+}
+
+//The error for finding `found` where the grammar wanted `expected`.
+pub fn unexpected(found: &Token, expected: &str, recent: &VecDeque<Token>) -> CalcError
+{
+    CalcError::UnexpectedToken{
+        found: found.clone(),
+        expected: String::from(expected),
+        after: reassemble(&recent.iter().cloned().collect::<Vec<Token>>()),
+    }
+}
+
+//Whether `name` can be assigned to or used as a parameter: not `@` or a
+//template placeholder such as `$1`.
+fn assignable(name: &str) -> bool
+{
+    name != "@" && !name.starts_with('$')
+}
+
+//Check the argument count of every call to a built-in function in
+//`node`, so `sqrt(1, 2)` fails before anything is evaluated. Calls to a
+//name in `shadowed`, the functions of a session that replace built-ins,
+//are left for the interpreter to check against the replacement.
+fn check_calls(node: &Ast, shadowed: &HashSet<String>) -> Result<(), CalcError>
+{
+    match node
+    {
+        Ast::Call(name, args) => {
+            if let (false, Some(builtin)) = (shadowed.contains(name), find_builtin(name)){
+                check_arity(builtin.name, builtin.arity, args.len())?;
+            }
+            args.iter().try_for_each(|arg| check_calls(arg, shadowed))
+        }
+        Ast::UnaryOp(_, operand) => check_calls(operand, shadowed),
+        Ast::BinOp(left, _, right) => {
+            check_calls(left, shadowed)?;
+            check_calls(right, shadowed)
+        }
+        Ast::If(condition, then, otherwise) => {
+            check_calls(condition, shadowed)?;
+            check_calls(then, shadowed)?;
+            check_calls(otherwise, shadowed)
+        }
+        Ast::Assign(_, value) => check_calls(value, shadowed),
+        //The head of a definition lists parameters; it isn't a call.
+        Ast::FunctionDef(_, _, body) => check_calls(body, shadowed),
+        Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Variable(_) => Ok(()),
+    }
+}
+
+pub struct Parser<'a>
+{
+    lexer: &'a mut Lexer,
+    current_token: Token,
+    //The last few tokens consumed before `current_token`, oldest first.
+    recent: VecDeque<Token>,
+    //How many `factor` calls are currently on the stack. Every way of
+    //nesting one expression in another goes through `factor`.
+    nesting: usize,
+    //The lexer failed on the token after `current_token`, which has already
+    //been consumed.
+    lexer_failed: bool,
+    //Function names whose calls aren't checked against a built-in's arity.
+    shadowed: HashSet<String>
+}
+
+impl<'a> Parser<'a>
+{
+    fn eat(&mut self) -> Result<(), CalcError>
+    {
+        let next_token = match self.lexer.get_next_token()
+        {
+            Ok(token) => token,
+            Err(e) => {
+                self.lexer_failed = true;
+                return Err(e);
+            }
+        };
+        remember(&mut self.recent, std::mem::replace(&mut self.current_token, next_token));
+        Ok(())
+    }
+
+    fn unexpected(&self, expected: &str) -> CalcError
+    {
+        unexpected(&self.current_token, expected, &self.recent)
+    }
+
+    //Consume the current token if it is `expected`, fail otherwise.
+    fn expect(&mut self, expected: Token) -> Result<(), CalcError>
+    {
+        if self.current_token != expected{
+            return Err(self.unexpected(&format!("'{}'", expected.to_str())));
+        }
+        self.eat()
+    }
+
+    //call : IDENTIFIER LPAREN (disjunction (COMMA disjunction)*)? RPAREN
+    fn call(&mut self, name: String) -> Result<Ast, CalcError>
+    {
+        self.expect(Token::Lparen)?;
+
+        let mut args = Vec::new();
+        if self.current_token != Token::Rparen{
+            args.push(self.disjunction()?);
+            while self.current_token == Token::Comma{
+                self.eat()?;
+                args.push(self.disjunction()?);
+            }
+        }
+        self.expect(Token::Rparen)?;
+
+        Ok(Ast::Call(name, args))
+    }
+
+    //atom : NUMBER | BOOL | call | IDENTIFIER | AT | PLACEHOLDER | conditional | LPAREN disjunction RPAREN
+    //conditional : IF disjunction THEN disjunction ELSE disjunction
+    //
+    //        The else branch reaches as far as it can, so
+    //        `if c then 1 else 2 + 3` adds 3 only when c is false.
+    fn atom(&mut self) -> Result<Ast, CalcError>
+    {
+        match self.current_token.clone()
+        {
+            Token::Integer(integer) => {self.eat()?; Ok(Ast::Integer(integer))},
+            Token::Float(float) => {self.eat()?; Ok(Ast::Float(float))},
+            Token::Bool(boolean) => {self.eat()?; Ok(Ast::Bool(boolean))},
+            Token::Identifier(name) => {
+                self.eat()?;
+                if self.current_token == Token::Lparen{
+                    return self.call(name);
+                }
+                Ok(Ast::Variable(name))
+            }
+            Token::At => {self.eat()?; Ok(Ast::Variable(String::from("@")))},
+            //Placeholders are variables with names no assignment can bind.
+            Token::Placeholder(index) => {self.eat()?; Ok(Ast::Variable(format!("${}", index)))},
+            Token::If => {
+                self.eat()?;
+                let condition = self.disjunction()?;
+                self.expect(Token::Then)?;
+                let then = self.disjunction()?;
+                self.expect(Token::Else)?;
+                let otherwise = self.disjunction()?;
+                Ok(Ast::If(Box::new(condition), Box::new(then), Box::new(otherwise)))
+            }
+            Token::Lparen => {
+                self.eat()?;
+                if self.current_token == Token::Rparen{
+                    return Err(CalcError::EmptyParentheses);
+                }
+                let node = self.disjunction()?;
+                self.expect(Token::Rparen)?;
+                Ok(node)
+            }
+            //The input ended where an operand was expected, e.g. `-` or `3 *`.
+            Token::Eof => match self.recent.back()
+            {
+                Some(operator) => Err(CalcError::MissingOperand(operator.to_str())),
+                None => Err(CalcError::EmptyExpression),
+            },
+            _ => Err(self.unexpected("a number, a name or '('"))
+        }
+    }
+
+    //percent : atom PERCENT*
+    //
+    //        `20%` is 20 / 100 as a float, so `20% of 150` is 30.0.
+    fn percent(&mut self) -> Result<Ast, CalcError>
+    {
+        let mut node = self.atom()?;
+
+        while self.current_token == Token::Percent{
+            self.eat()?;
+            node = Ast::UnaryOp(Token::Percent, Box::new(node));
+        }
+
+        Ok(node)
+    }
+
+    //power : percent (POWER factor)?
+    //
+    //        The exponent is a whole factor, which makes `^` right-associative
+    //        (2 ^ 3 ^ 2 is 2 ^ 9) and lets it carry a sign (2 ^ -1).
+    fn power(&mut self) -> Result<Ast, CalcError>
+    {
+        let node = self.percent()?;
+
+        if self.current_token == Token::Power{
+            self.eat()?;
+            return Ok(Ast::BinOp(Box::new(node), Token::Power, Box::new(self.factor()?)));
+        }
+
+        Ok(node)
+    }
+
+    //factor : (PLUS | MINUS | NOT) factor | power
+    //
+    //        Unary signs bind looser than `^`, so -2 ^ 2 is -(2 ^ 2). Signs
+    //        are operators rather than part of a number and may be stacked:
+    //        3++5 is 3 + (+5) = 8 and 3+-5 is 3 + (-5) = -2.
+    fn factor(&mut self) -> Result<Ast, CalcError>
+    {
+        if self.nesting >= MAX_DEPTH{
+            return Err(CalcError::TooDeeplyNested(MAX_DEPTH));
+        }
+        self.nesting += 1;
+        let node = self.signed_factor();
+        self.nesting -= 1;
+        node
+    }
+
+    fn signed_factor(&mut self) -> Result<Ast, CalcError>
+    {
+        match self.current_token
+        {
+            Token::Plus | Token::Minus | Token::Not => {
+                let operator = self.current_token.clone();
+                self.eat()?;
+                Ok(Ast::UnaryOp(operator, Box::new(self.factor()?)))
+            }
+            _ => self.power()
+        }
+    }
+
+    //term : factor ((MUL | DIV | OF) factor)*
+    //
+    //        `of` multiplies, so `10% of 200 + 5` is (10% of 200) + 5.
+    fn term(&mut self) -> Result<Ast, CalcError>
+    {
+        let mut node = self.factor()?;
+
+        while self.current_token == Token::Mul || self.current_token == Token::Div || self.current_token == Token::Of{
+            let operator = self.current_token.clone();
+            self.eat()?;
+            node = Ast::BinOp(Box::new(node), operator, Box::new(self.factor()?));
+        }
+
+        Ok(node)
+    }
+
+    //Arithmetic expression parser.
+    //
+    //        calc> 7 + 3 * (10 / (12 / (3 + 1) - 1))
+    //        22
+    //
+    //        disjunction : conjunction (OR conjunction)*
+    //        conjunction : comparison (AND comparison)*
+    //        comparison  : expr ((EQUAL | NOT_EQUAL | LESS | LESS_EQUAL | GREATER | GREATER_EQUAL) expr)?
+    //        expr        : term ((PLUS | MINUS) term)*
+    //        term        : factor ((MUL | DIV | OF) factor)*
+    //        factor      : (PLUS | MINUS | NOT) factor | power
+    //        power       : percent (POWER factor)?
+    //        percent     : atom PERCENT*
+    //        atom        : NUMBER | BOOL | call | IDENTIFIER | LPAREN disjunction RPAREN
+    //        call        : IDENTIFIER LPAREN (disjunction (COMMA disjunction)*)? RPAREN
+    fn expr(&mut self) -> Result<Ast, CalcError>
+    {
+        let mut node = self.term()?;
+
+        while self.current_token == Token::Plus || self.current_token == Token::Minus{
+            let operator = self.current_token.clone();
+            self.eat()?;
+            node = Ast::BinOp(Box::new(node), operator, Box::new(self.term()?));
+        }
+
+        Ok(node)
+    }
+
+    //comparison : expr (comparison_operator expr)?
+    //
+    //        Comparisons bind looser than arithmetic and don't chain:
+    //        `1 < 2 < 3` is an error rather than a surprise. They evaluate
+    //        to true or false.
+    fn comparison(&mut self) -> Result<Ast, CalcError>
+    {
+        let node = self.expr()?;
+
+        if self.current_token.is_comparison(){
+            let operator = self.current_token.clone();
+            self.eat()?;
+            return Ok(Ast::BinOp(Box::new(node), operator, Box::new(self.expr()?)));
+        }
+
+        Ok(node)
+    }
+
+    //conjunction : comparison (AND comparison)*
+    fn conjunction(&mut self) -> Result<Ast, CalcError>
+    {
+        let mut node = self.comparison()?;
+
+        while self.current_token == Token::And{
+            self.eat()?;
+            node = Ast::BinOp(Box::new(node), Token::And, Box::new(self.comparison()?));
+        }
+
+        Ok(node)
+    }
+
+    //disjunction : conjunction (OR conjunction)*
+    //
+    //        `&&` binds tighter than `||`, as in C, and both only evaluate
+    //        their right side when the left one doesn't decide the result.
+    fn disjunction(&mut self) -> Result<Ast, CalcError>
+    {
+        let mut node = self.conjunction()?;
+
+        while self.current_token == Token::Or{
+            self.eat()?;
+            node = Ast::BinOp(Box::new(node), Token::Or, Box::new(self.conjunction()?));
+        }
+
+        Ok(node)
+    }
+
+    //statement  : assignment | definition | disjunction
+    //assignment : IDENTIFIER ASSIGN statement
+    //definition : IDENTIFIER LPAREN (IDENTIFIER (COMMA IDENTIFIER)*)? RPAREN ASSIGN disjunction
+    //
+    //        Assignment is right-associative, so `a = b = 5` parses as
+    //        Assign(a, Assign(b, 5)) and sets both variables to 5. The
+    //        left side is parsed as an expression first and must turn out
+    //        to be a plain variable name, or a call whose arguments are all
+    //        plain names (`square(x) = x * x` defines a function).
+    fn statement(&mut self) -> Result<Ast, CalcError>
+    {
+        let node = self.disjunction()?;
+        if self.current_token != Token::Assign{
+            return Ok(node);
+        }
+
+        match node
+        {
+            Ast::Variable(name) if assignable(&name) => {
+                self.eat()?;
+                Ok(Ast::Assign(name, Box::new(self.statement()?)))
+            }
+            Ast::Call(name, args) => {
+                let params = args.into_iter()
+                    .map(|arg| match arg
+                    {
+                        Ast::Variable(param) if assignable(&param) => Ok(param),
+                        _ => Err(CalcError::InvalidAssignmentTarget),
+                    })
+                    .collect::<Result<Vec<String>, CalcError>>()?;
+                self.eat()?;
+                Ok(Ast::FunctionDef(name, params, Box::new(self.disjunction()?)))
+            }
+            _ => Err(CalcError::InvalidAssignmentTarget),
+        }
+    }
+
+    //Checks on a complete statement that aren't part of the grammar.
+    fn finish(&mut self, node: Ast) -> Result<Ast, CalcError>
+    {
+        //Operator chains are built in loops rather than by recursion, so
+        //measure the finished tree as well.
+        if height(&node) > MAX_HEIGHT{
+            return Err(CalcError::TooDeeplyNested(MAX_HEIGHT));
+        }
+        //A definition replaces the built-in for itself and for the
+        //statements parsed after it.
+        if let Ast::FunctionDef(name, _, _) = &node{
+            self.shadowed.insert(name.clone());
+        }
+        check_calls(&node, &self.shadowed)?;
+        debug_assert_eq!(validate(&node), Ok(()), "the parser built a malformed tree: {:?}", node);
+        Ok(node)
+    }
+
+    //Parse a whole statement, requiring that it consumes all of the input.
+    pub fn parse(&mut self) -> Result<Ast, CalcError>
+    {
+        let node = self.statement()?;
+        if self.current_token != Token::Eof{
+            return Err(self.unexpected("an operator or the end of the input"));
+        }
+        self.finish(node)
+    }
+
+    //Parse the next statement of `statement (SEMICOLON statement)*`, with
+    //the (character) range of the input it was read from, or return `None`
+    //at the end of the input. Empty statements, as in `1;;2`, are skipped.
+    //
+    //        The `;` after a statement is only consumed by the next call,
+    //        so a statement can be evaluated before anything after it is
+    //        lexed.
+    pub fn next_statement(&mut self) -> Result<Option<(Ast, Range<usize>)>, CalcError>
+    {
+        while self.current_token == Token::Semicolon{
+            self.eat()?;
+        }
+        if self.current_token == Token::Eof{
+            return Ok(None);
+        }
+        //Errors quote tokens of this statement only.
+        self.recent.clear();
+
+        let start = self.position();
+        let node = self.statement()?;
+        if self.current_token != Token::Semicolon && self.current_token != Token::Eof{
+            return Err(self.unexpected("an operator, ';' or the end of the input"));
+        }
+        let node = self.finish(node)?;
+        Ok(Some((node, start..self.position())))
+    }
+
+    //Skip the rest of a statement that failed to parse, up to the next
+    //`;` or the end of the input, so `next_statement` can carry on after
+    //it. Characters the lexer rejects are skipped too.
+    pub fn recover(&mut self)
+    {
+        let mut stale = std::mem::take(&mut self.lexer_failed);
+        while stale || (self.current_token != Token::Semicolon && self.current_token != Token::Eof){
+            match self.lexer.get_next_token()
+            {
+                Ok(token) => {
+                    remember(&mut self.recent, std::mem::replace(&mut self.current_token, token));
+                    stale = false;
+                }
+                Err(_e) => self.lexer.skip_char(),
+            }
+        }
+    }
+
+    //Where the current token starts in the input, in characters.
+    pub fn position(&self) -> usize
+    {
+        self.lexer.token_start()
+    }
+
+    //Parse a comma-separated list of expressions,
+    //`disjunction (COMMA disjunction)*`, requiring that it consumes all of
+    //the input.
+    pub fn parse_list(&mut self) -> Result<Vec<Ast>, CalcError>
+    {
+        let mut nodes = vec![self.disjunction()?];
+        while self.current_token == Token::Comma{
+            self.eat()?;
+            nodes.push(self.disjunction()?);
+        }
+        if self.current_token != Token::Eof{
+            return Err(self.unexpected("an operator, ',' or the end of the input"));
+        }
+        if nodes.iter().any(|node| height(node) > MAX_HEIGHT){
+            return Err(CalcError::TooDeeplyNested(MAX_HEIGHT));
+        }
+        nodes.iter().try_for_each(|node| check_calls(node, &self.shadowed))?;
+        debug_assert!(nodes.iter().all(|node| validate(node).is_ok()), "the parser built a malformed tree: {:?}", nodes);
+        Ok(nodes)
+    }
+
+    pub fn create_parser(lexer: &mut Lexer) -> Result<Parser<'_>, CalcError>
+    {
+        let cur_token = lexer.get_next_token()?;
+
+        Ok(Parser{
+            lexer,
+            current_token: cur_token,
+            recent: VecDeque::new(),
+            nesting: 0,
+            lexer_failed: false,
+            shadowed: HashSet::new(),
+        })
+    }
+
+    //Leave calls to `names` unchecked, because the session defines
+    //functions of its own by those names:
+    //
+    //        Parser::create_parser(&mut lexer)?.shadowing(names).parse()
+    pub fn shadowing<I>(mut self, names: I) -> Parser<'a>
+        where I: IntoIterator<Item=String>
+    {
+        self.shadowed.extend(names);
+        self
+    }
+
+    //A parser for `next_statement` that starts as if just after a `;`, so
+    //even the first token is lexed by `next_statement` and a character the
+    //lexer rejects there can be recovered from.
+    pub fn create_statement_parser(lexer: &mut Lexer) -> Parser<'_>
+    {
+        Parser{
+            lexer,
+            current_token: Token::Semicolon,
+            recent: VecDeque::new(),
+            nesting: 0,
+            lexer_failed: false,
+            shadowed: HashSet::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{evaluate, evaluate_fast, parse, Arity, CalcError, Calculator, Token, Value};
+
+    use super::{MAX_DEPTH, MAX_HEIGHT};
+
+    //Nest `wrap` around `1` more and more deeply until the parser refuses,
+    //on a thread with the default 2 MB stack of spawned threads, so the
+    //deepest input that is accepted has been evaluated without
+    //overflowing the stack.
+    fn deepest_accepted(wrap: fn(&str) -> String, limit: usize) -> usize
+    {
+        std::thread::Builder::new()
+            .stack_size(2 << 20)
+            .spawn(move || {
+                let mut input = String::from("1");
+                for depth in 0..{
+                    match evaluate(&input)
+                    {
+                        Err(CalcError::TooDeeplyNested(found)) => {
+                            assert_eq!(found, limit, "{}", input);
+                            return depth - 1;
+                        }
+                        result => {
+                            assert!(result.is_ok(), "{}: {:?}", input, result);
+                            assert_eq!(evaluate_fast(&input), result.map(|outcome| outcome.value), "{}", input);
+                        }
+                    }
+                    input = wrap(&input);
+                }
+                unreachable!()
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn nesting_up_to_the_limit_fits_on_a_small_stack()
+    {
+        assert_eq!(deepest_accepted(|inner| format!("({})", inner), MAX_DEPTH), MAX_DEPTH - 1);
+        assert_eq!(deepest_accepted(|inner| format!("sqrt({})", inner), MAX_DEPTH), MAX_DEPTH - 1);
+        assert_eq!(deepest_accepted(|inner| format!("if true then {} else 0", inner), MAX_DEPTH), MAX_DEPTH - 1);
+        assert_eq!(deepest_accepted(|inner| format!("if ({}) > 0 then 1 else 0", inner), MAX_DEPTH), (MAX_DEPTH - 1) / 2);
+        assert_eq!(deepest_accepted(|inner| format!("-{}", inner), MAX_DEPTH), MAX_DEPTH - 1);
+    }
+
+    #[test]
+    fn long_chains_up_to_the_height_limit_fit_on_a_small_stack()
+    {
+        assert_eq!(deepest_accepted(|inner| format!("{} + 1", inner), MAX_HEIGHT), MAX_HEIGHT - 1);
+    }
+
+    #[test]
+    fn chained_assignments_set_every_variable()
+    {
+        let mut calculator = Calculator::new();
+        calculator.eval("a = b = 5").unwrap();
+        assert_eq!(calculator.variable("a"), Some(&Value::Int(5)));
+        assert_eq!(calculator.variable("b"), Some(&Value::Int(5)));
+    }
+
+    #[test]
+    fn only_names_can_be_assigned_to()
+    {
+        assert_eq!(evaluate("2 = 3"), Err(CalcError::InvalidAssignmentTarget));
+        assert_eq!(evaluate("x + 1 = 3"), Err(CalcError::InvalidAssignmentTarget));
+        assert_eq!(evaluate("f(1) = 3"), Err(CalcError::InvalidAssignmentTarget));
+    }
+
+    #[test]
+    fn lone_operators_and_empty_groups()
+    {
+        assert_eq!(evaluate("-"), Err(CalcError::MissingOperand(String::from("-"))));
+        assert_eq!(evaluate("-  "), Err(CalcError::MissingOperand(String::from("-"))));
+        assert_eq!(evaluate("-()"), Err(CalcError::EmptyParentheses));
+        assert_eq!(evaluate("()"), Err(CalcError::EmptyParentheses));
+    }
+
+    #[test]
+    fn arguments_are_full_expressions()
+    {
+        assert_eq!(evaluate("sqrt(pow(3, 2) + pow(4, 2))").unwrap().value, Value::Float(5.0));
+        assert_eq!(evaluate("max(1 + 1, 2 * 2)").unwrap().value, Value::Int(4));
+        assert_eq!(evaluate("min(max(1, -(2 - 5)), signum(-7) + 5)").unwrap().value, Value::Int(3));
+    }
+
+    #[test]
+    fn signs_can_follow_binary_operators()
+    {
+        for (input, expected) in [("3++5", 8), ("3+-5", -2), ("3--5", 8), ("3-+5", -2), ("3 + - - 5", 8), ("3*-2", -6)]{
+            assert_eq!(evaluate(input).unwrap().value, Value::Int(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn syntax_errors_show_the_preceding_tokens()
+    {
+        let error = evaluate("3 + )").unwrap_err();
+        assert_eq!(error, CalcError::UnexpectedToken{
+            found: Token::Rparen,
+            expected: String::from("a number, a name or '('"),
+            after: String::from("3 +"),
+        });
+        assert_eq!(error.to_string(), "after '3 +', expected a number, a name or '(' but found ')'");
+        assert_eq!(evaluate(")").unwrap_err().to_string(), "expected a number, a name or '(' but found ')'");
+    }
+
+    #[test]
+    fn argument_counts_are_checked_against_the_function_called()
+    {
+        //The errors come from `parse`, before anything is evaluated.
+        assert_eq!(parse("sqrt(1, 2)").unwrap_err().to_string(), "sqrt expects 1 argument, got 2");
+        assert_eq!(parse("atan2(1)").unwrap_err().to_string(), "atan2 expects 2 arguments, got 1");
+        assert_eq!(parse("max()").unwrap_err().to_string(), "max expects at least 1 argument, got 0");
+        assert_eq!(parse("1 + sqrt(copysign(1), 2)").unwrap_err().to_string(), "sqrt expects 1 argument, got 2");
+        assert_eq!(evaluate("1 / 0 + sqrt(1, 2)").unwrap_err().to_string(), "sqrt expects 1 argument, got 2");
+
+        let mut calculator = Calculator::new();
+        assert!(calculator.eval("y = sqrt(1, 2)").is_err());
+        assert_eq!(calculator.variable("y"), None);
+
+        calculator.register_function("sqrt", Arity::Exactly(2), Box::new(|args| Ok(args[0] + args[1])));
+        assert_eq!(calculator.eval("sqrt(1, 2)").unwrap().into_value(), Some(Value::Float(3.0)));
+
+        calculator.eval("max(x, y) = x - y").unwrap();
+        assert_eq!(calculator.eval("max(5, 2)").unwrap().into_value(), Some(Value::Int(3)));
+        assert!(matches!(calculator.eval("max(1, 2, 3)"), Err(CalcError::WrongArgumentCount{ .. })));
+
+        //A definition counts from the next statement of the same input.
+        let mut values = Vec::new();
+        calculator.eval_statements("atan2(y) = y; atan2(7)", |_, result| values.push(result.unwrap().into_value()));
+        assert_eq!(values, vec![None, Some(Value::Int(7))]);
+    }
+
+    #[test]
+    fn conditionals_need_both_branches()
+    {
+        assert_eq!(evaluate("if 1 < 2 then 10 else 20").unwrap().value, Value::Int(10));
+        assert_eq!(evaluate("if 1 > 2 then 10 else 20").unwrap().value, Value::Int(20));
+        assert_eq!(evaluate("if false then 1 / 0 else 3").unwrap().value, Value::Int(3));
+        assert_eq!(evaluate("if 1 < 2 then 10"), Err(CalcError::UnexpectedToken{
+            found: Token::Eof,
+            expected: String::from("'else'"),
+            after: String::from("2 then 10"),
+        }));
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//Small pseudo-random generator (SplitMix64) behind `random()` and
+//`randint(a, b)`. Not suitable for cryptography, but fast, and the same
+//seed always produces the same sequence.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Rng
+{
+    state: u64
+}
+
+impl Rng
+{
+    pub fn create_rng(seed: u64) -> Rng
+    {
+        Rng{
+            state: seed,
+        }
+    }
+
+    //A generator seeded from the clock, for sessions that didn't ask for
+    //a particular seed.
+    pub fn from_clock() -> Rng
+    {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Rng::create_rng(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64
+    {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    //A float in [0, 1), using the top 53 bits so every value is equally likely.
+    pub fn next_float(&mut self) -> f64
+    {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    //An integer in `low..=high`, which must not be empty.
+    //
+    //        Draws falling in the incomplete last block of the u64 range are
+    //        rejected so that no value is favoured.
+    pub fn range(&mut self, low: i64, high: i64) -> i64
+    {
+        let span = high.wrapping_sub(low) as u64;
+        if span == u64::MAX{
+            return self.next_u64() as i64;
+        }
+
+        let size = span + 1;
+        let limit = u64::MAX - u64::MAX % size;
+        loop {
+            let draw = self.next_u64();
+            if draw < limit{
+                return low.wrapping_add((draw % size) as i64);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{Calculator, Value};
+
+    fn rolls(calculator: &mut Calculator) -> Vec<i64>
+    {
+        (0..20).map(|_| match calculator.eval("randint(1, 6)").unwrap().into_value()
+        {
+            Some(Value::Int(roll)) => roll,
+            other => panic!("randint gave {:?}", other),
+        }).collect()
+    }
+
+    #[test]
+    fn a_fixed_seed_repeats_the_same_rolls()
+    {
+        let mut calculator = Calculator::builder().seed(42).build();
+        let first = rolls(&mut calculator);
+        assert!(first.iter().all(|roll| (1..=6).contains(roll)), "{:?}", first);
+        assert!(first.iter().any(|roll| *roll != first[0]), "{:?}", first);
+
+        assert_eq!(rolls(&mut Calculator::builder().seed(42).build()), first);
+        calculator.seed(42);
+        assert_eq!(rolls(&mut calculator), first);
+        assert_ne!(rolls(&mut Calculator::builder().seed(7).build()), first);
+    }
+}
@@ -0,0 +1,138 @@
+use std::convert::TryFrom;
+
+use crate::error::CalcError;
+use crate::functions::{call_function, constant, find_builtin, Arity};
+use crate::interpreter::{binary_op, Interpreter};
+use crate::lexer::tokenize;
+use crate::options::{Angle, Options};
+use crate::random::Rng;
+use crate::token::Token;
+use crate::value::Value;
+
+//Take the top `count` values off `stack`, deepest first, on behalf of
+//`operator`.
+fn pop(stack: &mut Vec<Value>, count: usize, operator: &str) -> Result<Vec<Value>, CalcError>
+{
+    if stack.len() < count{
+        return Err(CalcError::StackUnderflow(String::from(operator)));
+    }
+    Ok(stack.split_off(stack.len() - count))
+}
+
+//Evaluate space-separated reverse Polish notation with a value stack:
+//`2 3 4 * +` is 14.
+//
+//        Accepts what `to_rpn` prints: numbers, booleans and constants,
+//        binary operators, `neg`, `pos`, `!` and `%` for the unary ones,
+//        `if` taking a condition and two values, and function names,
+//        written `max/3` (with the argument count) for functions that take
+//        a variable number of arguments. An operator with too few values
+//        below it is a `StackUnderflow`, and anything but exactly one
+//        value left at the end is `LeftoverOperands`.
+pub fn evaluate_rpn(input: &str) -> Result<Value, CalcError>
+{
+    Options::default().check_input(input)?;
+    let tokens = tokenize(input)?;
+    let mut rng = Rng::from_clock();
+    let mut stack = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len(){
+        let token = &tokens[i];
+        i += 1;
+        let value = match token
+        {
+            Token::Integer(integer) => Value::Int(*integer),
+            Token::Float(float) => Value::Float(*float),
+            Token::Bool(boolean) => Value::Bool(*boolean),
+            Token::Not | Token::Percent => {
+                let operand = pop(&mut stack, 1, &token.to_str())?.remove(0);
+                Interpreter::unary_op(token, operand)?
+            }
+            Token::Identifier(name) if name == "neg" || name == "pos" => {
+                let operand = pop(&mut stack, 1, name)?.remove(0);
+                let operator = if name == "neg" { Token::Minus } else { Token::Plus };
+                Interpreter::unary_op(&operator, operand)?
+            }
+            Token::If => {
+                let mut operands = pop(&mut stack, 3, "if")?;
+                let otherwise = operands.pop().unwrap();
+                let then = operands.pop().unwrap();
+                if operands[0].as_bool()? { then } else { otherwise }
+            }
+            Token::Identifier(name) => match (find_builtin(name), constant(name))
+            {
+                (Some(builtin), _) => {
+                    //Only a function with a variable number of arguments
+                    //takes a count, so `4 16 sqrt / 2 /` divides by 2.
+                    let count = match (builtin.arity, tokens.get(i), tokens.get(i + 1))
+                    {
+                        (Arity::Exactly(count), _, _) => count,
+                        (Arity::AtLeast(_), Some(Token::Div), Some(Token::Integer(count))) => {
+                            i += 2;
+                            usize::try_from(*count).map_err(|_e| CalcError::Overflow)?
+                        }
+                        (Arity::AtLeast(_), _, _) => return Err(CalcError::TypeError(format!(
+                            "{} takes a variable number of arguments, write {}/n for n of them", name, name
+                        ))),
+                    };
+                    if builtin.lazy{
+                        return Err(CalcError::TypeError(format!("{} is not supported in RPN", name)));
+                    }
+                    let args = pop(&mut stack, count, name)?;
+                    call_function(name, args, Angle::Radians, &mut rng)?
+                }
+                (None, Some(constant)) => constant,
+                (None, None) => return Err(CalcError::UnknownVariable(name.clone())),
+            },
+            operator if operator.is_comparison() || operator.is_logical() || matches!(operator,
+                Token::Plus | Token::Minus | Token::Mul | Token::Div | Token::Power | Token::Of) => {
+                let operands = pop(&mut stack, 2, &operator.to_str())?;
+                binary_op(operator, &operands[0], &operands[1])?
+            }
+            found => return Err(CalcError::UnexpectedToken{
+                found: found.clone(),
+                expected: String::from("a number, an operator or a function name"),
+                after: String::new(),
+            }),
+        };
+        stack.push(value);
+    }
+
+    match stack.len()
+    {
+        0 => Err(CalcError::EmptyExpression),
+        1 => Ok(stack.pop().unwrap()),
+        left => Err(CalcError::LeftoverOperands(left)),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{evaluate, parse, to_rpn, CalcError, Value};
+
+    use super::evaluate_rpn;
+
+    #[test]
+    fn a_division_after_a_fixed_arity_function_is_a_division()
+    {
+        assert_eq!(evaluate_rpn("4 16 sqrt / 2 +"), Ok(Value::Float(3.0)));
+        assert_eq!(evaluate_rpn("8 4 sqrt / 2 /"), Ok(Value::Float(2.0)));
+        assert_eq!(evaluate_rpn("1 5 3 max/3 2 /"), Ok(Value::Int(2)));
+        assert!(matches!(evaluate_rpn("1 2 max"), Err(CalcError::TypeError(_))));
+    }
+
+    #[test]
+    fn printed_rpn_evaluates_to_the_same_value()
+    {
+        let inputs = [
+            "2 + 3 * 4", "-(1 + 2) ^ 2", "8 / sqrt(4) / 2", "max(1, 5, 3) / 2", "min(4, 2) + sqrt(16) / 2",
+            "if 1 < 2 then 10 else 20", "10% of 200", "!(1 == 2) && true", "divmod(7, 2)", "pow(2, 10) - pi",
+        ];
+        for input in inputs{
+            let rpn = to_rpn(&parse(input).unwrap());
+            assert_eq!(evaluate_rpn(&rpn), evaluate(input).map(|outcome| outcome.value), "{} as {}", input, rpn);
+        }
+    }
+}
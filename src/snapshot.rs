@@ -0,0 +1,338 @@
+use crate::ast::Ast;
+#[cfg(feature = "bignum")]
+use crate::bignum::BigInt;
+use crate::environment::UserFunction;
+use crate::error::CalcError;
+use crate::value::Value;
+
+const HEADER: &str = "rust_calculator snapshot 1";
+
+//An input line that was evaluated successfully, along with its result.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub struct HistoryEntry
+{
+    pub input: String,
+    pub result: Value
+}
+
+impl HistoryEntry
+{
+    pub fn create_history_entry(input: &str, result: Value) -> HistoryEntry
+    {
+        HistoryEntry{ input: String::from(input), result }
+    }
+}
+
+//A saved copy of a calculator's variables, functions, memory register
+//and history.
+//
+//        Snapshots serialize to a small line-based text format so a host
+//        can write a session to disk and load it back later:
+//
+//        rust_calculator snapshot 1
+//        variable x i5
+//        function square(y) = y * y
+//        memory i25
+//        history i5 x = 5
+//        history i25 square(x)
+//
+//        Values are tagged: `i` for integers, `f` for floats (stored as
+//        the hex of their bit pattern so they round-trip exactly), `b`
+//        for big integers, `t(..)` for tuples and `true` / `false` for
+//        booleans. Newlines and backslashes in history inputs are escaped
+//        as `\n`, `\r` and `\\`.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub struct Snapshot
+{
+    pub variables: Vec<(String, Value)>,
+    //User-defined functions as definitions that parse back to them, such
+    //as `square(y) = y * y`.
+    pub functions: Vec<String>,
+    pub memory: Value,
+    pub history: Vec<HistoryEntry>
+}
+
+//Parse a definition saved in `Snapshot::functions`.
+pub fn parse_definition(definition: &str) -> Result<(String, UserFunction), CalcError>
+{
+    match crate::parse(definition)?
+    {
+        Ast::FunctionDef(name, params, body) => Ok((name, UserFunction{ params, body: *body })),
+        _ => Err(CalcError::InvalidSnapshot(format!("'{}' is not a function definition", definition))),
+    }
+}
+
+//Put a history input on one line.
+fn escape(input: &str) -> String
+{
+    let mut escaped = String::new();
+    for c in input.chars(){
+        match c
+        {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(escaped: &str) -> Result<String, CalcError>
+{
+    let mut input = String::new();
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next(){
+        if c != '\\'{
+            input.push(c);
+            continue;
+        }
+        match chars.next()
+        {
+            Some('\\') => input.push('\\'),
+            Some('n') => input.push('\n'),
+            Some('r') => input.push('\r'),
+            _ => return Err(CalcError::InvalidSnapshot(format!("malformed escape in '{}'", escaped))),
+        }
+    }
+    Ok(input)
+}
+
+fn encode_value(value: &Value, out: &mut String)
+{
+    match value
+    {
+        Value::Int(integer) => out.push_str(&format!("i{}", integer)),
+        Value::Float(float) => out.push_str(&format!("f{:016x}", float.to_bits())),
+        Value::Bool(boolean) => out.push_str(&boolean.to_string()),
+        #[cfg(feature = "bignum")]
+        Value::BigInt(big) => out.push_str(&format!("b{}", big)),
+        Value::Tuple(values) => {
+            out.push_str("t(");
+            for (i, value) in values.iter().enumerate(){
+                if i > 0{
+                    out.push(',');
+                }
+                encode_value(value, out);
+            }
+            out.push(')');
+        }
+    }
+}
+
+//Decode one value from the start of `text`, returning it with the unread remainder.
+fn decode_value(text: &str) -> Result<(Value, &str), CalcError>
+{
+    let invalid = || CalcError::InvalidSnapshot(format!("malformed value '{}'", text));
+    let end = |rest: &str| rest.find([',', ')', ' ']).unwrap_or(rest.len());
+
+    if let Some(rest) = text.strip_prefix("t("){
+        let mut values = Vec::new();
+        let mut rest = rest;
+        if let Some(after) = rest.strip_prefix(')'){
+            return Ok((Value::Tuple(values), after));
+        }
+        loop {
+            let (value, after) = decode_value(rest)?;
+            values.push(value);
+            match after.chars().next()
+            {
+                Some(',') => rest = &after[1..],
+                Some(')') => return Ok((Value::Tuple(values), &after[1..])),
+                _ => return Err(invalid()),
+            }
+        }
+    }
+
+    for boolean in [true, false]{
+        if let Some(after) = text.strip_prefix(boolean.to_string().as_str()){
+            return Ok((Value::Bool(boolean), after));
+        }
+    }
+
+    if let Some(rest) = text.strip_prefix('i'){
+        let (digits, after) = rest.split_at(end(rest));
+        let integer = digits.parse().map_err(|_e| invalid())?;
+        return Ok((Value::Int(integer), after));
+    }
+
+    if let Some(rest) = text.strip_prefix('f'){
+        let (digits, after) = rest.split_at(end(rest));
+        let bits = u64::from_str_radix(digits, 16).map_err(|_e| invalid())?;
+        return Ok((Value::Float(f64::from_bits(bits)), after));
+    }
+
+    #[cfg(feature = "bignum")]
+    if let Some(rest) = text.strip_prefix('b'){
+        let (digits, after) = rest.split_at(end(rest));
+        let big = BigInt::parse(digits).ok_or_else(invalid)?;
+        return Ok((Value::BigInt(big), after));
+    }
+
+    Err(invalid())
+}
+
+//Decode a value that makes up all of `text`.
+fn decode_whole_value(text: &str) -> Result<Value, CalcError>
+{
+    match decode_value(text)?
+    {
+        (value, "") => Ok(value),
+        _ => Err(CalcError::InvalidSnapshot(format!("malformed value '{}'", text))),
+    }
+}
+
+impl Snapshot
+{
+    //A snapshot with nothing saved yet: no variables, functions or
+    //history, and a memory register of 0.
+    pub fn create_snapshot() -> Snapshot
+    {
+        Snapshot{
+            variables: Vec::new(),
+            functions: Vec::new(),
+            memory: Value::Int(0),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn serialize(&self) -> String
+    {
+        let mut out = String::from(HEADER);
+        out.push('\n');
+
+        for (name, value) in &self.variables{
+            out.push_str("variable ");
+            out.push_str(name);
+            out.push(' ');
+            encode_value(value, &mut out);
+            out.push('\n');
+        }
+
+        for definition in &self.functions{
+            out.push_str("function ");
+            out.push_str(definition);
+            out.push('\n');
+        }
+
+        out.push_str("memory ");
+        encode_value(&self.memory, &mut out);
+        out.push('\n');
+
+        for entry in &self.history{
+            out.push_str("history ");
+            encode_value(&entry.result, &mut out);
+            out.push(' ');
+            out.push_str(&escape(&entry.input));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    pub fn deserialize(text: &str) -> Result<Snapshot, CalcError>
+    {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER){
+            return Err(CalcError::InvalidSnapshot(String::from("missing header")));
+        }
+
+        let mut snapshot = Snapshot::create_snapshot();
+
+        for line in lines{
+            if let Some(rest) = line.strip_prefix("variable "){
+                let (name, encoded) = rest.split_once(' ')
+                    .ok_or_else(|| CalcError::InvalidSnapshot(format!("malformed line '{}'", line)))?;
+                snapshot.variables.push((String::from(name), decode_whole_value(encoded)?));
+            } else if let Some(definition) = line.strip_prefix("function "){
+                parse_definition(definition)?;
+                snapshot.functions.push(String::from(definition));
+            } else if let Some(encoded) = line.strip_prefix("memory "){
+                snapshot.memory = decode_whole_value(encoded)?;
+            } else if let Some(rest) = line.strip_prefix("history "){
+                let (result, input) = decode_value(rest)?;
+                snapshot.history.push(HistoryEntry{
+                    input: unescape(input.strip_prefix(' ').unwrap_or(input))?,
+                    result,
+                });
+            } else if !line.is_empty(){
+                return Err(CalcError::InvalidSnapshot(format!("malformed line '{}'", line)));
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{CalcError, Calculator, Value};
+
+    use super::Snapshot;
+
+    #[test]
+    fn restore_undoes_later_changes()
+    {
+        let mut calculator = Calculator::new();
+        calculator.eval("x = 5").unwrap();
+        calculator.eval("square(y) = y * y").unwrap();
+        calculator.eval("square(x)").unwrap();
+        calculator.memory_add().unwrap();
+        let snapshot = calculator.snapshot();
+
+        calculator.eval("x = 6").unwrap();
+        calculator.eval("z = 1").unwrap();
+        calculator.eval("square(y) = 0").unwrap();
+        calculator.memory_clear();
+
+        calculator.restore(&snapshot).unwrap();
+        assert_eq!(calculator.variable("x"), Some(&Value::Int(5)));
+        assert_eq!(calculator.variable("z"), None);
+        assert_eq!(calculator.eval("square(3)").unwrap().into_value(), Some(Value::Int(9)));
+        assert_eq!(calculator.memory(), &Value::Int(25));
+        assert_eq!(calculator.history().len(), snapshot.history.len() + 1);
+    }
+
+    #[test]
+    fn serialized_snapshots_read_back()
+    {
+        let mut calculator = Calculator::new();
+        calculator.eval("x = 1\n+ 2").unwrap();
+        calculator.eval("half(n) = n / 2.0").unwrap();
+        calculator.eval("t = divmod(7, 2)").unwrap();
+        calculator.eval("x").unwrap();
+        calculator.memory_add().unwrap();
+        let snapshot = calculator.snapshot();
+
+        assert_eq!(Snapshot::deserialize(&snapshot.serialize()), Ok(snapshot.clone()));
+
+        let mut restored = Calculator::new();
+        restored.restore(&Snapshot::deserialize(&snapshot.serialize()).unwrap()).unwrap();
+        assert_eq!(restored.history()[0].input, "x = 1\n+ 2");
+        assert_eq!(restored.eval("half(x) + mem").unwrap().into_value(), Some(Value::Float(4.5)));
+    }
+
+    #[test]
+    fn malformed_snapshots_are_rejected()
+    {
+        let header = "rust_calculator snapshot 1\n";
+        assert!(Snapshot::deserialize("variable x i5\n").is_err());
+        assert!(Snapshot::deserialize(&format!("{}variable x\n", header)).is_err());
+        assert!(Snapshot::deserialize(&format!("{}function x = 2\n", header)).is_err());
+        assert!(Snapshot::deserialize(&format!("{}history i1 1 \\q 2\n", header)).is_err());
+    }
+
+    #[test]
+    fn values_followed_by_junk_are_rejected()
+    {
+        let header = "rust_calculator snapshot 1\n";
+        assert_eq!(Snapshot::deserialize(&format!("{}variable x i3 garbage\n", header)),
+            Err(CalcError::InvalidSnapshot(String::from("malformed value 'i3 garbage'"))));
+        assert!(Snapshot::deserialize(&format!("{}variable t t(i1,i2)i3\n", header)).is_err());
+        assert!(Snapshot::deserialize(&format!("{}memory true 1\n", header)).is_err());
+        assert!(Snapshot::deserialize(&format!("{}variable x i3\nmemory i4\n", header)).is_ok());
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use crate::ast::{free_variables, Ast};
+use crate::error::CalcError;
+use crate::value::Value;
+
+//An expression with numbered placeholders, parsed once and evaluated
+//with different values for them:
+//
+//        let template = Template::create_template("$1 + $2 * $3")?;
+//        template.fill(&[Value::Int(1), Value::Int(2), Value::Int(3)])  ->  Ok(Int(7))
+//
+//        `$n` takes the n-th value. Extra values are ignored; too few are a
+//        `MissingPlaceholder` error naming the lowest placeholder used that
+//        has no value.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Template
+{
+    ast: Ast,
+    //The placeholder numbers used, in increasing order.
+    used: Vec<usize>
+}
+
+impl Template
+{
+    pub fn create_template(input: &str) -> Result<Template, CalcError>
+    {
+        let ast = crate::parse(input)?;
+        let mut used = free_variables(&ast).iter()
+            .filter_map(|name| name.strip_prefix('$')?.parse().ok())
+            .collect::<Vec<usize>>();
+        used.sort_unstable();
+        Ok(Template{ ast, used })
+    }
+
+    //How many values `fill` needs: the highest placeholder number used.
+    pub fn placeholders(&self) -> usize
+    {
+        self.used.last().copied().unwrap_or(0)
+    }
+
+    //Evaluate the template with `values[0]` as `$1`, `values[1]` as `$2`
+    //and so on.
+    pub fn fill(&self, values: &[Value]) -> Result<Value, CalcError>
+    {
+        if let Some(missing) = self.used.iter().find(|index| **index > values.len()){
+            return Err(CalcError::MissingPlaceholder(*missing));
+        }
+        let bindings = values.iter()
+            .enumerate()
+            .map(|(i, value)| (format!("${}", i + 1), value.clone()))
+            .collect::<HashMap<String, Value>>();
+        crate::eval_ast_with(&self.ast, &bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{CalcError, Value};
+
+    use super::Template;
+
+    #[test]
+    fn placeholders_take_the_values_in_order()
+    {
+        let template = Template::create_template("$1 * $2").unwrap();
+        assert_eq!(template.placeholders(), 2);
+        assert_eq!(template.fill(&[Value::Int(6), Value::Int(7)]), Ok(Value::Int(42)));
+        assert_eq!(template.fill(&[Value::Int(6), Value::Float(0.5), Value::Int(1)]), Ok(Value::Float(3.0)));
+    }
+
+    #[test]
+    fn the_first_used_placeholder_without_a_value_is_reported()
+    {
+        let template = Template::create_template("$1 * $2").unwrap();
+        assert_eq!(template.fill(&[Value::Int(6)]), Err(CalcError::MissingPlaceholder(2)));
+        assert_eq!(template.fill(&[]), Err(CalcError::MissingPlaceholder(1)));
+
+        let template = Template::create_template("$3 + $10").unwrap();
+        assert_eq!(template.placeholders(), 10);
+        assert_eq!(template.fill(&[Value::Int(1)]), Err(CalcError::MissingPlaceholder(3)));
+        assert_eq!(template.fill(&[Value::Int(1), Value::Int(2), Value::Int(3)]), Err(CalcError::MissingPlaceholder(10)));
+    }
+}
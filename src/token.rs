@@ -0,0 +1,135 @@
+use std::fmt;
+
+//One token of the input. New operators and keywords add tokens, so a
+//`match` outside this crate needs a `_` arm.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub enum Token
+{
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Identifier(String),
+    //`$1`, `$2`, ...: a value supplied when a `Template` is filled.
+    Placeholder(usize),
+    //`@`, the previous result on the same line.
+    At,
+    Plus,
+    Minus,
+    Mul,
+    Div,
+    Power,
+    Percent,
+    Of,
+    If,
+    Then,
+    Else,
+    Lparen,
+    Rparen,
+    Comma,
+    Semicolon,
+    Assign,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Not,
+    And,
+    Or,
+    Eof
+}
+
+impl Token
+{
+    pub fn is_comparison(&self) -> bool
+    {
+        matches!(self, Token::Equal | Token::NotEqual | Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual)
+    }
+
+    pub fn is_logical(&self) -> bool
+    {
+        matches!(self, Token::And | Token::Or)
+    }
+
+    //Canonical source form of the token, which lexes back to the same
+    //token. Whole floats keep a `.0` so they don't come back as integers.
+    pub fn to_str(&self) -> String
+    {
+        match self
+        {
+            Token::Integer(integer) => integer.to_string(),
+            Token::Float(float) if float.fract() == 0.0 => format!("{:.1}", float),
+            Token::Float(float) => float.to_string(),
+            Token::Bool(boolean) => boolean.to_string(),
+            Token::Identifier(name) => name.clone(),
+            Token::Placeholder(index) => format!("${}", index),
+            Token::Plus => String::from("+"),
+            Token::Minus => String::from("-"),
+            Token::Mul => String::from("*"),
+            Token::Div => String::from("/"),
+            Token::Power => String::from("^"),
+            Token::Percent => String::from("%"),
+            Token::Of => String::from("of"),
+            Token::If => String::from("if"),
+            Token::Then => String::from("then"),
+            Token::Else => String::from("else"),
+            Token::Lparen => String::from("("),
+            Token::Rparen => String::from(")"),
+            Token::Comma => String::from(","),
+            Token::Semicolon => String::from(";"),
+            Token::At => String::from("@"),
+            Token::Assign => String::from("="),
+            Token::Equal => String::from("=="),
+            Token::NotEqual => String::from("!="),
+            Token::Less => String::from("<"),
+            Token::LessEqual => String::from("<="),
+            Token::Greater => String::from(">"),
+            Token::GreaterEqual => String::from(">="),
+            Token::Not => String::from("!"),
+            Token::And => String::from("&&"),
+            Token::Or => String::from("||"),
+            Token::Eof => String::new(),
+        }
+    }
+}
+
+//Human-readable form for messages: the source form of the token, except
+//for `Eof`, which reads as "end of input".
+impl fmt::Display for Token
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Token::Eof => write!(f, "end of input"),
+            token => write!(f, "{}", token.to_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Token;
+
+    #[test]
+    fn every_token_displays_as_its_source_form()
+    {
+        let cases = [
+            (Token::Integer(42), "42"), (Token::Float(2.0), "2.0"), (Token::Float(0.25), "0.25"),
+            (Token::Bool(true), "true"), (Token::Identifier(String::from("x1")), "x1"), (Token::Placeholder(2), "$2"),
+            (Token::At, "@"), (Token::Plus, "+"), (Token::Minus, "-"), (Token::Mul, "*"), (Token::Div, "/"),
+            (Token::Power, "^"), (Token::Percent, "%"), (Token::Of, "of"), (Token::If, "if"), (Token::Then, "then"),
+            (Token::Else, "else"), (Token::Lparen, "("), (Token::Rparen, ")"), (Token::Comma, ","),
+            (Token::Semicolon, ";"), (Token::Assign, "="), (Token::Equal, "=="), (Token::NotEqual, "!="),
+            (Token::Less, "<"), (Token::LessEqual, "<="), (Token::Greater, ">"), (Token::GreaterEqual, ">="),
+            (Token::Not, "!"), (Token::And, "&&"), (Token::Or, "||"), (Token::Eof, "end of input"),
+        ];
+        for (token, expected) in cases{
+            assert_eq!(token.to_string(), expected, "{:?}", token);
+        }
+        assert_eq!(Token::Eof.to_str(), "");
+    }
+}
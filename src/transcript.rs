@@ -0,0 +1,92 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//One line typed at the prompt and what was printed for it (nothing for a
+//quiet assignment).
+pub struct Entry
+{
+    pub input: String,
+    pub output: Option<String>,
+    pub time: SystemTime
+}
+
+//`time` as a UTC date and time, e.g. 2024-03-01 14:05:09.
+fn utc(time: SystemTime) -> String
+{
+    let seconds = time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    let (days, second_of_day) = (seconds / 86400, seconds % 86400);
+
+    //Days since 1970-01-01 to a civil date, counting in 400-year eras of
+    //146097 days that start on March 1st so leap days come last.
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day,
+        second_of_day / 3600, second_of_day / 60 % 60, second_of_day % 60)
+}
+
+//The text `:save` writes: each input after a `> ` prompt, followed by its
+//output lines.
+//
+//        > x = 2; x * 3
+//        x = 2
+//        6
+//
+//        With `timestamps` every input is preceded by the UTC time it was
+//        entered, as in `[2024-03-01 14:05:09] > x = 2; x * 3`.
+pub fn serialize(entries: &[Entry], timestamps: bool) -> String
+{
+    let mut text = String::new();
+    for entry in entries{
+        if timestamps{
+            text.push_str(&format!("[{}] ", utc(entry.time)));
+        }
+        text.push_str(&format!("> {}\n", entry.input));
+        if let Some(output) = &entry.output{
+            text.push_str(output);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{serialize, utc, Entry};
+
+    fn entry(input: &str, output: Option<&str>, seconds: u64) -> Entry
+    {
+        Entry{
+            input: String::from(input),
+            output: output.map(String::from),
+            time: UNIX_EPOCH + Duration::from_secs(seconds),
+        }
+    }
+
+    #[test]
+    fn transcripts_list_each_input_and_its_output()
+    {
+        let entries = [entry("x = 2; x * 3", Some("x = 2\n6"), 1_709_301_909), entry("y = 1", None, 1_709_301_910)];
+        assert_eq!(serialize(&entries, false), "> x = 2; x * 3\nx = 2\n6\n> y = 1\n");
+        assert_eq!(serialize(&entries, true), "[2024-03-01 14:05:09] > x = 2; x * 3\nx = 2\n6\n[2024-03-01 14:05:10] > y = 1\n");
+        assert_eq!(serialize(&[], true), "");
+    }
+
+    #[test]
+    fn times_are_shown_as_utc_dates()
+    {
+        assert_eq!(utc(UNIX_EPOCH), "1970-01-01 00:00:00");
+        //A leap day, and the last second of a leap year.
+        assert_eq!(utc(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29 00:00:00");
+        assert_eq!(utc(UNIX_EPOCH + Duration::from_secs(1_735_689_599)), "2024-12-31 23:59:59");
+    }
+}
@@ -0,0 +1,390 @@
+use std::fmt;
+use std::convert::TryFrom;
+
+#[cfg(feature = "bignum")]
+use crate::bignum::BigInt;
+use crate::error::CalcError;
+use crate::options::{Base, Options, Rounding};
+
+//Fractional digits in the exact decimal expansion of the smallest f64;
+//no finite float needs more.
+const EXACT_DIGITS: usize = 1074;
+
+//Add one unit in the last place to a string of decimal digits, which may
+//contain a point: `9.99` gives `10.00`.
+fn increment(digits: &str) -> String
+{
+    let mut out = digits.as_bytes().to_vec();
+    for digit in out.iter_mut().rev(){
+        match *digit
+        {
+            b'.' => continue,
+            b'9' => *digit = b'0',
+            _ => {
+                *digit += 1;
+                return String::from_utf8(out).unwrap();
+            }
+        }
+    }
+    format!("1{}", String::from_utf8(out).unwrap())
+}
+
+//Show the finite `float` with `precision` digits after the decimal point.
+//
+//        Every mode rounds the exact binary value, so they only disagree on
+//        ties: 2.675 is stored as 2.67499999..., which is 2.67 under
+//        half-up as well as half-even. A result that rounds to zero has no
+//        sign, so -0.04 at one digit is 0.0 (and -0.1 with `Floor`).
+fn round_float(float: f64, precision: usize, rounding: Rounding) -> String
+{
+    let negative = float.is_sign_negative();
+    let magnitude = if rounding == Rounding::HalfEven || precision >= EXACT_DIGITS{
+        format!("{:.*}", precision, float.abs())
+    } else {
+        let exact = format!("{:.*}", EXACT_DIGITS, float.abs());
+        let point = exact.find('.').unwrap();
+        let (kept, dropped) = exact.split_at(if precision == 0 { point } else { point + 1 + precision });
+        let dropped = dropped.trim_start_matches('.');
+        let inexact = dropped.bytes().any(|digit| digit != b'0');
+        let round_up = match rounding
+        {
+            Rounding::HalfUp => dropped.as_bytes()[0] >= b'5',
+            Rounding::TowardZero => false,
+            Rounding::Ceil => inexact && !negative,
+            Rounding::Floor => inexact && negative,
+            Rounding::HalfEven => unreachable!("handled above"),
+        };
+        if round_up { increment(kept) } else { String::from(kept) }
+    };
+
+    if negative && magnitude.bytes().any(|digit| digit.is_ascii_digit() && digit != b'0'){
+        format!("-{}", magnitude)
+    } else {
+        magnitude
+    }
+}
+
+//The simplest fraction p/q with q <= `max_denominator` that equals
+//`float` to within a relative 1e-9, as `(p, q)`.
+//
+//        Walks the continued fraction of `float`, whose convergents are the
+//        best approximations for their denominator size: 0.75 is 3/4 and
+//        1.0 / 3 is 1/3, while pi only gets as close as 355/113, which isn't
+//        close enough, so it has no fraction.
+fn to_fraction(float: f64, max_denominator: u64) -> Option<(i64, u64)>
+{
+    if !float.is_finite(){
+        return None;
+    }
+    let tolerance = 1e-9 * float.abs().max(1.0);
+
+    //Consecutive convergents h/k, starting from 1/0 and 0/1.
+    let (mut h, mut h_prev) = (1i128, 0i128);
+    let (mut k, mut k_prev) = (0i128, 1i128);
+    let mut rest = float;
+    loop {
+        let whole = rest.floor();
+        if whole.abs() > i64::MAX as f64{
+            return None;
+        }
+        let (h_next, k_next) = (whole as i128 * h + h_prev, whole as i128 * k + k_prev);
+        if k_next > max_denominator as i128{
+            return None;
+        }
+        (h_prev, h, k_prev, k) = (h, h_next, k, k_next);
+
+        if (float - h as f64 / k as f64).abs() <= tolerance{
+            return Some((i64::try_from(h).ok()?, k as u64));
+        }
+        rest = 1.0 / (rest - whole);
+        if !rest.is_finite(){
+            return None;
+        }
+    }
+}
+
+//Result of evaluating an expression.
+//
+//        Integer-only expressions stay integers; as soon as a float is
+//        involved the result is promoted to a float. Built-ins that
+//        compute several things at once (like `divmod`) return a tuple.
+//        Comparisons give a `Bool`, which is not a number: `true + 1` is a
+//        type error, and `&&`, `||`, `!` and `assert` only take booleans.
+//        With the `bignum` feature, integers too large for an i64 are kept
+//        exactly as a `BigInt`; results that fit are always `Int`. Since
+//        any crate in a build can turn that feature on, a `match` outside
+//        this crate needs a `_` arm.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub enum Value
+{
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    #[cfg(feature = "bignum")]
+    BigInt(BigInt),
+    Tuple(Vec<Value>)
+}
+
+impl Value
+{
+    //Wrap a big integer, narrowing it back to an `Int` when it fits.
+    #[cfg(feature = "bignum")]
+    pub fn from_bigint(big: BigInt) -> Value
+    {
+        match big.to_i64()
+        {
+            Some(integer) => Value::Int(integer),
+            None => Value::BigInt(big),
+        }
+    }
+
+    pub fn as_int(&self) -> Result<i64, CalcError>
+    {
+        match self
+        {
+            Value::Int(integer) => Ok(*integer),
+            Value::Float(_) => Err(CalcError::TypeError(String::from("expected an integer, found a float"))),
+            Value::Bool(_) => Err(CalcError::TypeError(String::from("expected a number, found a boolean"))),
+            #[cfg(feature = "bignum")]
+            Value::BigInt(_) => Err(CalcError::Overflow),
+            Value::Tuple(_) => Err(CalcError::TypeError(String::from("expected a number, found a tuple"))),
+        }
+    }
+
+    pub fn as_float(&self) -> Result<f64, CalcError>
+    {
+        match self
+        {
+            Value::Int(integer) => Ok(*integer as f64),
+            Value::Float(float) => Ok(*float),
+            Value::Bool(_) => Err(CalcError::TypeError(String::from("expected a number, found a boolean"))),
+            #[cfg(feature = "bignum")]
+            Value::BigInt(big) => Ok(big.to_f64()),
+            Value::Tuple(_) => Err(CalcError::TypeError(String::from("expected a number, found a tuple"))),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, CalcError>
+    {
+        match self
+        {
+            Value::Bool(boolean) => Ok(*boolean),
+            Value::Tuple(_) => Err(CalcError::TypeError(String::from("expected a boolean, found a tuple"))),
+            _ => Err(CalcError::TypeError(String::from("expected a boolean, found a number"))),
+        }
+    }
+
+    //Display the value using the base, precision and rounding in `options`.
+    //
+    //        Negative integers keep their sign in front of the prefix, so
+    //        -255 in hex is -0xff. Big integers are always shown in decimal.
+    pub fn format(&self, options: &Options) -> String
+    {
+        match self
+        {
+            Value::Int(integer) => {
+                let sign = if *integer < 0 { "-" } else { "" };
+                let magnitude = integer.unsigned_abs();
+                match options.base
+                {
+                    Base::Binary => format!("{}0b{:b}", sign, magnitude),
+                    Base::Octal => format!("{}0o{:o}", sign, magnitude),
+                    Base::Decimal => integer.to_string(),
+                    Base::Hex => format!("{}0x{:x}", sign, magnitude),
+                }
+            }
+            Value::Float(float) => match (options.max_denominator.and_then(|limit| to_fraction(*float, limit)), options.precision)
+            {
+                (Some((numerator, denominator)), _) if denominator > 1 => format!("{}/{}", numerator, denominator),
+                (_, Some(precision)) if float.is_finite() => round_float(*float, precision, options.rounding),
+                _ => self.to_string(),
+            },
+            Value::Bool(_) => self.to_string(),
+            #[cfg(feature = "bignum")]
+            Value::BigInt(_) => self.to_string(),
+            Value::Tuple(values) => {
+                let values = values.iter().map(|value| value.format(options)).collect::<Vec<String>>();
+                format!("({})", values.join(", "))
+            }
+        }
+    }
+}
+
+//Result of evaluating one statement.
+//
+//        Assignments remember which variable they set so the REPL can
+//        echo them as `x = 5`; plain expressions display as the bare value.
+//        Function definitions produce no value.
+#[derive(PartialEq, Clone, Debug)]
+pub enum StatementResult
+{
+    Assignment(String, Value),
+    Definition(String),
+    Expression(Value)
+}
+
+impl StatementResult
+{
+    pub fn value(&self) -> Option<&Value>
+    {
+        match self
+        {
+            StatementResult::Assignment(_, value) => Some(value),
+            StatementResult::Definition(_) => None,
+            StatementResult::Expression(value) => Some(value),
+        }
+    }
+
+    pub fn into_value(self) -> Option<Value>
+    {
+        match self
+        {
+            StatementResult::Assignment(_, value) => Some(value),
+            StatementResult::Definition(_) => None,
+            StatementResult::Expression(value) => Some(value),
+        }
+    }
+
+    //Like the `Display` form, with values shown using `options`.
+    pub fn format(&self, options: &Options) -> String
+    {
+        match self
+        {
+            StatementResult::Assignment(name, value) => format!("{} = {}", name, value.format(options)),
+            StatementResult::Definition(name) => format!("defined {}", name),
+            StatementResult::Expression(value) => value.format(options),
+        }
+    }
+}
+
+//Everything `evaluate` reports about one input.
+//
+//        For `7/2` the value is 3, with a warning that the division was
+//        truncated, and the expression is the canonical `7 / 2`.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub struct EvalOutcome
+{
+    pub value: Value,
+    //Notes raised while evaluating, such as truncated integer divisions.
+    pub warnings: Vec<String>,
+    //The input rewritten from its tokens, with canonical spacing.
+    pub expression: String
+}
+
+impl EvalOutcome
+{
+    pub fn create_outcome(value: Value, warnings: Vec<String>, expression: String) -> EvalOutcome
+    {
+        EvalOutcome{ value, warnings, expression }
+    }
+}
+
+impl fmt::Display for StatementResult
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            StatementResult::Assignment(name, value) => write!(f, "{} = {}", name, value),
+            StatementResult::Definition(name) => write!(f, "defined {}", name),
+            StatementResult::Expression(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for Value
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Value::Int(integer) => write!(f, "{}", integer),
+            //Keep a trailing `.0` so whole floats are not mistaken for integers.
+            Value::Float(float) if float.is_finite() && float.fract() == 0.0 => write!(f, "{:.1}", float),
+            Value::Float(float) => write!(f, "{}", float),
+            Value::Bool(boolean) => write!(f, "{}", boolean),
+            #[cfg(feature = "bignum")]
+            Value::BigInt(big) => write!(f, "{}", big),
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+                for (i, value) in values.iter().enumerate(){
+                    if i > 0{
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{evaluate, Calculator, Options};
+
+    use crate::Rounding;
+
+    use super::{round_float, to_fraction, StatementResult, Value};
+
+    #[test]
+    fn assignments_show_the_variable_and_expressions_the_bare_value()
+    {
+        let assignment = StatementResult::Assignment(String::from("x"), Value::Int(5));
+        assert_eq!(assignment.to_string(), "x = 5");
+        assert_eq!(assignment.format(&Options::default()), "x = 5");
+        assert_eq!(StatementResult::Expression(Value::Int(5)).to_string(), "5");
+        assert_eq!(StatementResult::Definition(String::from("f")).to_string(), "defined f");
+
+        let mut calculator = Calculator::new();
+        let result = calculator.eval("x = 2 + 3").unwrap();
+        assert_eq!(calculator.format(&result), "x = 5");
+        let result = calculator.eval("x * 2").unwrap();
+        assert_eq!(calculator.format(&result), "10");
+    }
+
+    #[test]
+    fn outcomes_carry_the_value_warnings_and_canonical_expression()
+    {
+        let outcome = evaluate("7/2").unwrap();
+        assert_eq!(outcome.value, Value::Int(3));
+        assert_eq!(outcome.warnings, vec![String::from("7 / 2 truncated to 3 (use a float operand such as 7.0 / 2 for the exact result)")]);
+        assert_eq!(outcome.expression, "7 / 2");
+    }
+
+    #[test]
+    fn every_rounding_mode_rounds_the_exact_value()
+    {
+        let modes = [Rounding::HalfUp, Rounding::HalfEven, Rounding::TowardZero, Rounding::Ceil, Rounding::Floor];
+        let cases: [(f64, usize, [&str; 5]); 7] = [
+            (2.5, 0, ["3", "2", "2", "3", "2"]),
+            (-2.5, 0, ["-3", "-2", "-2", "-2", "-3"]),
+            (2.675, 2, ["2.67", "2.67", "2.67", "2.68", "2.67"]),
+            (0.125, 2, ["0.13", "0.12", "0.12", "0.13", "0.12"]),
+            (-0.04, 1, ["0.0", "0.0", "0.0", "0.0", "-0.1"]),
+            (9.999, 2, ["10.00", "10.00", "9.99", "10.00", "9.99"]),
+            (3.0, 1, ["3.0", "3.0", "3.0", "3.0", "3.0"]),
+        ];
+        for (float, precision, expected) in cases{
+            for (rounding, expected) in modes.iter().zip(expected){
+                assert_eq!(round_float(float, precision, *rounding), expected, "{} to {} digits, {:?}", float, precision, rounding);
+            }
+        }
+        assert_eq!(round_float(-0.0, 2, Rounding::HalfEven), "0.00");
+    }
+
+    #[test]
+    fn floats_are_matched_to_simple_fractions()
+    {
+        assert_eq!(to_fraction(0.5, 1000), Some((1, 2)));
+        assert_eq!(to_fraction(1.0 / 3.0, 1000), Some((1, 3)));
+        assert_eq!(to_fraction(0.333_333_333_333, 1000), Some((1, 3)));
+        assert_eq!(to_fraction(-0.75, 1000), Some((-3, 4)));
+        assert_eq!(to_fraction(2.0, 1000), Some((2, 1)));
+        assert_eq!(to_fraction(std::f64::consts::PI, 1000), None);
+        assert_eq!(to_fraction(0.001, 100), None);
+    }
+}
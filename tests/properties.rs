@@ -0,0 +1,135 @@
+//Property tests: random expressions over integers and + - * / must
+//evaluate to what a direct walk of the tree they were printed from gives,
+//so any precedence or associativity mistake in the parser shows up as a
+//mismatch. proptest shrinks a failing expression to a minimal one.
+
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use rust_calculator::{evaluate, evaluate_fast, CalcError, Value};
+
+const MAX_DEPTH: u32 = 5;
+
+#[derive(Clone, Debug)]
+enum Expr
+{
+    Number(i64),
+    //How it is printed is part of the case: `spaced` puts spaces around
+    //the operator and `grouped` adds parentheses nothing requires.
+    BinOp{ left: Box<Expr>, operator: char, right: Box<Expr>, spaced: bool, grouped: bool }
+}
+
+fn precedence(operator: char) -> u32
+{
+    match operator
+    {
+        '+' | '-' => 1,
+        '*' | '/' => 2,
+        _ => unreachable!("not an operator: {}", operator),
+    }
+}
+
+fn binop(left: Expr, operator: char, right: Expr, spaced: bool, grouped: bool) -> Expr
+{
+    Expr::BinOp{ left: Box::new(left), operator, right: Box::new(right), spaced, grouped }
+}
+
+//Trees of operands from 0 to 50, at most MAX_DEPTH operators deep.
+fn expr() -> impl Strategy<Value=Expr>
+{
+    (0i64..=50).prop_map(Expr::Number).prop_recursive(MAX_DEPTH, 64, 2, |inner| {
+        (inner.clone(), select(vec!['+', '-', '*', '/']), inner, any::<bool>(), prop::bool::weighted(0.125))
+            .prop_map(|(left, operator, right, spaced, grouped)| binop(left, operator, right, spaced, grouped))
+    })
+}
+
+//A flat chain of one precedence level, such as `7 - 2 + 5 - 1`.
+fn chain() -> impl Strategy<Value=Expr>
+{
+    (any::<bool>(), 1i64..=20, prop::collection::vec((any::<bool>(), 1i64..=20, any::<bool>()), 1..=6))
+        .prop_map(|(additive, first, rest)| {
+            let operators = if additive { ['+', '-'] } else { ['*', '/'] };
+            rest.into_iter().fold(Expr::Number(first), |chain, (second, number, spaced)| {
+                binop(chain, operators[second as usize], Expr::Number(number), spaced, false)
+            })
+        })
+}
+
+//The reference semantics: integer arithmetic with division truncating
+//toward zero. `None` if some step overflows an i64, where the result
+//depends on the `bignum` feature.
+fn reference(expr: &Expr) -> Option<Result<i64, CalcError>>
+{
+    match expr
+    {
+        Expr::Number(number) => Some(Ok(*number)),
+        Expr::BinOp{ left, operator, right, .. } => {
+            let (left, right) = match (reference(left)?, reference(right)?)
+            {
+                (Ok(left), Ok(right)) => (left, right),
+                (Err(e), _) | (_, Err(e)) => return Some(Err(e)),
+            };
+            let result = match operator
+            {
+                '+' => left.checked_add(right),
+                '-' => left.checked_sub(right),
+                '*' => left.checked_mul(right),
+                '/' if right == 0 => return Some(Err(CalcError::DivisionByZero)),
+                '/' => left.checked_div(right),
+                _ => unreachable!("not an operator: {}", operator),
+            };
+            result.map(Ok)
+        }
+    }
+}
+
+//Print `expr` with the parentheses precedence and left associativity
+//require, plus the redundant ones it asks for.
+fn render(expr: &Expr) -> String
+{
+    match expr
+    {
+        Expr::Number(number) => number.to_string(),
+        Expr::BinOp{ left, operator, right, spaced, .. } => {
+            let left_text = operand(left, |inner| inner < precedence(*operator));
+            let right_text = operand(right, |inner| inner <= precedence(*operator));
+            let space = if *spaced { " " } else { "" };
+            format!("{}{}{}{}{}", left_text, space, operator, space, right_text)
+        }
+    }
+}
+
+fn operand<F>(expr: &Expr, needs_parentheses: F) -> String
+    where F: Fn(u32) -> bool
+{
+    let text = render(expr);
+    match expr
+    {
+        Expr::BinOp{ operator, grouped, .. } if *grouped || needs_parentheses(precedence(*operator)) => format!("({})", text),
+        _ => text,
+    }
+}
+
+proptest!
+{
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn random_expressions_match_the_reference(expr in expr())
+    {
+        let expected = reference(&expr);
+        prop_assume!(expected.is_some());
+        let expected = expected.unwrap().map(Value::Int);
+        let input = render(&expr);
+
+        prop_assert_eq!(&evaluate(&input).map(|outcome| outcome.value), &expected, "evaluate({:?})", input);
+        prop_assert_eq!(&evaluate_fast(&input), &expected, "evaluate_fast({:?})", input);
+    }
+
+    #[test]
+    fn chains_are_left_associative(expr in chain())
+    {
+        let input = render(&expr);
+        prop_assert_eq!(evaluate(&input).map(|outcome| outcome.value), reference(&expr).unwrap().map(Value::Int), "evaluate({:?})", input);
+    }
+}
@@ -0,0 +1,134 @@
+//The public API as a dependent crate sees it: only items re-exported from
+//the crate root are used, so a change that breaks callers fails to build
+//or pass here.
+
+use std::collections::HashMap;
+
+use rust_calculator::{eval_ast_with, evaluate, parse, validate, Ast, CalcError, Calculator, HistoryEntry, Options, Snapshot, StatementResult, Token, Value};
+
+//A caller's view of an error: matching needs a catch-all arm because
+//`CalcError` is non-exhaustive.
+fn describe(error: &CalcError) -> &'static str
+{
+    match error
+    {
+        CalcError::DivisionByZero => "division by zero",
+        CalcError::UnknownVariable(_) => "unknown variable",
+        CalcError::UnexpectedToken { .. } => "syntax error",
+        _ => "other",
+    }
+}
+
+#[test]
+fn evaluate_returns_the_value_and_canonical_expression()
+{
+    let outcome = evaluate("2+3*4").unwrap();
+    assert_eq!(outcome.value, Value::Int(14));
+    assert_eq!(outcome.expression, "2 + 3 * 4");
+    assert!(outcome.warnings.is_empty());
+    assert_eq!(evaluate("1 < 2.5").unwrap().value, Value::Bool(true));
+}
+
+#[test]
+fn errors_can_be_matched_and_displayed()
+{
+    let error = evaluate("1 / 0").unwrap_err();
+    assert_eq!(describe(&error), "division by zero");
+    assert_eq!(error.to_string(), "division by zero");
+
+    assert_eq!(describe(&evaluate("x + 1").unwrap_err()), "unknown variable");
+    assert_eq!(describe(&evaluate("1 +)").unwrap_err()), "syntax error");
+
+    //`CalcError` is a standard error, so it works with `?` into a boxed error.
+    let boxed: Box<dyn std::error::Error> = Box::new(error);
+    assert_eq!(boxed.to_string(), "division by zero");
+}
+
+#[test]
+fn parse_builds_a_tree_that_can_be_evaluated_repeatedly()
+{
+    let ast = parse("x * x").unwrap();
+    assert_eq!(ast, Ast::BinOp(Box::new(Ast::Variable(String::from("x"))), Token::Mul, Box::new(Ast::Variable(String::from("x")))));
+
+    let bindings = HashMap::from([(String::from("x"), Value::Int(3))]);
+    assert_eq!(eval_ast_with(&ast, &bindings), Ok(Value::Int(9)));
+    assert_eq!(eval_ast_with(&ast, &bindings), Ok(Value::Int(9)));
+}
+
+#[test]
+fn values_convert_and_display()
+{
+    assert_eq!(Value::Int(3).as_float(), Ok(3.0));
+    assert!(Value::Float(0.5).as_int().is_err());
+    assert_eq!(Value::Float(2.0).to_string(), "2.0");
+    assert_eq!(Value::Bool(false).to_string(), "false");
+    assert_eq!(Value::Tuple(vec![Value::Int(3), Value::Int(1)]).to_string(), "(3, 1)");
+}
+
+//`Value` is non-exhaustive too, since the `bignum` feature adds a variant.
+fn is_whole(value: &Value) -> bool
+{
+    match value
+    {
+        Value::Int(_) => true,
+        Value::Float(float) => float.fract() == 0.0,
+        _ => false,
+    }
+}
+
+#[test]
+fn values_are_matched_with_a_catch_all_arm()
+{
+    assert!(is_whole(&Value::Int(3)));
+    assert!(!is_whole(&Value::Float(0.5)));
+    assert!(!is_whole(&Value::Bool(true)));
+}
+
+#[test]
+fn snapshots_are_built_with_their_constructors()
+{
+    let mut snapshot = Snapshot::create_snapshot();
+    snapshot.variables.push((String::from("x"), Value::Int(5)));
+    snapshot.history.push(HistoryEntry::create_history_entry("x = 5", Value::Int(5)));
+
+    let mut calculator = Calculator::new();
+    calculator.restore(&Snapshot::deserialize(&snapshot.serialize()).unwrap()).unwrap();
+    assert_eq!(calculator.eval("x + 1").unwrap().into_value(), Some(Value::Int(6)));
+}
+
+#[test]
+fn a_calculator_keeps_state_between_inputs()
+{
+    let mut calculator = Calculator::builder().precision(2).build();
+    assert_eq!(calculator.eval("r = 2").unwrap(), StatementResult::Assignment(String::from("r"), Value::Int(2)));
+    calculator.eval("area(r) = pi * r ^ 2").unwrap();
+
+    let result = calculator.eval("area(r)").unwrap();
+    assert_eq!(calculator.format(&result), "12.57");
+    assert_eq!(calculator.history().len(), 2);
+
+    calculator.reset();
+    assert!(calculator.eval("r").is_err());
+}
+
+#[test]
+fn options_start_from_the_defaults()
+{
+    let mut options = Options::default();
+    options.max_input_len = 4;
+    assert_eq!(rust_calculator::evaluate_with_options("1 + 2", &options).unwrap_err(), CalcError::InputTooLong(4));
+}
+
+#[test]
+fn validate_accepts_parsed_trees_and_rejects_malformed_ones()
+{
+    for input in ["1 + 2 * -x", "a = b = 3", "f(x, y) = if x < y then y else x", "max(1, 2%) ^ $1 || @ == 2"]{
+        assert_eq!(validate(&parse(input).unwrap()), Ok(()), "{}", input);
+    }
+
+    let one = || Box::new(Ast::Integer(1));
+    assert!(validate(&Ast::BinOp(one(), Token::Eof, one())).is_err());
+    assert!(validate(&Ast::UnaryOp(Token::Mul, one())).is_err());
+    assert!(validate(&Ast::Variable(String::from("2x"))).is_err());
+    assert!(validate(&Ast::BinOp(one(), Token::Plus, Box::new(Ast::Assign(String::from("x"), one())))).is_err());
+}